@@ -0,0 +1,283 @@
+use bevy::{platform::collections::{HashMap, HashSet}, prelude::*};
+use bevy_northstar::nav::Nav;
+
+use crate::{fog::FogOfWar, item::{ItemId, ItemRegistry, Placeable}, machine::Machine, mouse::GridClicked, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory}, recipe::RecipeRegistry, world::{Level, TILESIZE, WorldGrid}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Facing {
+    North,
+    East,
+    South,
+    West
+}
+
+impl Facing {
+    pub fn offset(&self) -> IVec2 {
+        match self {
+            Facing::North => IVec2::new(0, 1),
+            Facing::East => IVec2::new(1, 0),
+            Facing::South => IVec2::new(0, -1),
+            Facing::West => IVec2::new(-1, 0)
+        }
+    }
+
+    pub fn clockwise(&self) -> Facing {
+        match self {
+            Facing::North => Facing::East,
+            Facing::East => Facing::South,
+            Facing::South => Facing::West,
+            Facing::West => Facing::North
+        }
+    }
+
+    pub fn counter_clockwise(&self) -> Facing {
+        self.clockwise().clockwise().clockwise()
+    }
+
+    pub const ALL: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+}
+
+pub fn step(cell: UVec3, facing: Facing) -> Option<UVec3> {
+    let next = cell.truncate().as_ivec2() + facing.offset();
+    if next.x < 0 || next.y < 0 { return None; }
+    Some(UVec3::new(next.x as u32, next.y as u32, cell.z))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeltKind {
+    Straight,
+    Splitter, // alternates between the two sides of its facing
+    Merger // forward only, takes turns between the belts feeding it
+}
+
+#[derive(Debug, Component)]
+pub struct Belt {
+    pub kind: BeltKind,
+    pub facing: Facing,
+    pub cell: UVec3,
+    toggle: bool, // splitter side used last
+    last_input: Option<Facing> // merger side used last
+}
+
+impl Belt {
+    // cells the belt can push an item into, in the order it should try them
+    fn outputs(&self) -> Vec<Facing> {
+        match self.kind {
+            BeltKind::Straight | BeltKind::Merger => vec![self.facing],
+            BeltKind::Splitter => {
+                let (left, right) = (self.facing.counter_clockwise(), self.facing.clockwise());
+                if self.toggle { vec![right, left] } else { vec![left, right] }
+            }
+        }
+    }
+}
+
+// item entity riding on a belt
+#[derive(Debug, Component)]
+pub struct BeltItem { pub item: ItemId, pub cell: UVec3 }
+
+#[derive(Resource)]
+pub struct BeltIndex { pub cells: HashMap<UVec3, Entity> }
+
+#[derive(Resource)]
+struct BeltPlacement { facing: Facing }
+
+#[derive(Resource)]
+struct BeltClock(Timer);
+
+pub struct Belts;
+
+impl Plugin for Belts {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BeltIndex { cells: HashMap::new() });
+        app.insert_resource(BeltPlacement { facing: Facing::East });
+        app.insert_resource(BeltClock(Timer::from_seconds(BELT_STEP_SECS, TimerMode::Repeating)));
+
+        app.add_systems(Update, (rotate_belt_placement, place_belt, update_machine_ports));
+        app.add_systems(Update, (move_belt_items, emit_machine_outputs).chain());
+    }
+}
+
+const BELT_Z_AXIS: f32 = 2.0;
+const BELT_ITEM_Z_AXIS: f32 = 2.5;
+const BELT_STEP_SECS: f32 = 0.4; // time for an item to move one cell
+const MACHINE_INPUT_BATCHES: u32 = 2; // how many batches a machine buffers from its belts
+
+fn rotate_belt_placement(input: Res<ButtonInput<KeyCode>>, mut placement: ResMut<BeltPlacement>) {
+    if input.just_pressed(KeyCode::KeyR) {
+        placement.facing = placement.facing.clockwise();
+    }
+}
+
+fn place_belt(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    placement: Res<BeltPlacement>,
+    items: Res<ItemRegistry>,
+    mut inventory: ResMut<PlayerInventory>,
+    mut index: ResMut<BeltIndex>,
+    fog: Res<FogOfWar>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    let Some(Placeable::Belt(kind)) = items.get(item).placeable else { return; };
+
+    // belts stay walkable so workers can cross them
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+    if !matches!(grid.nav(cell), Some(Nav::Passable(_))) { return; }
+    if occupancy.is_taken(cell) { return; }
+    if !inventory.take_one(item) { return; }
+
+    let facing = placement.facing;
+    let arrow = facing.offset().as_vec2() * (TILESIZE as f32 / 3.0);
+
+    let belt = commands.spawn((
+        Name::new(format!("{:?} belt", kind)),
+        Belt { kind, facing, cell, toggle: false, last_input: None },
+        Level(cell.z),
+        Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, BELT_Z_AXIS),
+        Sprite { color: items.get(item).icon, custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)), ..default() },
+        children![(
+            Transform::from_xyz(arrow.x, arrow.y, 0.1),
+            Sprite { color: Color::srgb(0.85, 0.85, 0.85), custom_size: Some(Vec2::new(3.0, 3.0)), ..default() }
+        )]
+    )).id();
+
+    index.cells.insert(cell, belt);
+}
+
+// a belt next to a machine and facing away from it is an output port
+fn update_machine_ports(index: Res<BeltIndex>, belts: Query<&Belt>, mut machines: Query<&mut Machine>) {
+    for mut machine in &mut machines {
+        let cell = machine.cell;
+        let output_port = Facing::ALL.iter().any(|facing| {
+            step(cell, *facing)
+                .and_then(|next| index.cells.get(&next))
+                .and_then(|e| belts.get(*e).ok())
+                .is_some_and(|belt| belt.kind != BeltKind::Splitter && belt.facing == *facing)
+        });
+
+        if machine.output_port != output_port { machine.output_port = output_port; }
+    }
+}
+
+// every clock step each item tries to advance one cell. items blocked by the item in front get
+// another chance once it has moved, so a full belt still moves as a whole
+fn move_belt_items(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut clock: ResMut<BeltClock>,
+    index: Res<BeltIndex>,
+    recipes: Res<RecipeRegistry>,
+    mut belts: Query<&mut Belt>,
+    mut machines: Query<&mut Machine>,
+    mut items: Query<(Entity, &mut BeltItem, &mut Transform)>,
+) {
+    if !clock.0.tick(time.delta()).just_finished() { return; }
+
+    let mut occupied: HashMap<UVec3, Entity> = items.iter().map(|(e, i, _)| (i.cell, e)).collect();
+    let mut moved: HashSet<Entity> = HashSet::new();
+    let mut pass = 0;
+
+    loop {
+        let mut changed = false;
+
+        for (entity, mut belt_item, mut transform) in &mut items {
+            if moved.contains(&entity) { continue; }
+            let Some(belt_entity) = index.cells.get(&belt_item.cell) else { continue; };
+            let Ok(belt) = belts.get(*belt_entity) else { continue; };
+
+            let mut destination: Option<(UVec3, Facing)> = None;
+            for facing in belt.outputs() {
+                let Some(next) = step(belt_item.cell, facing) else { continue; };
+
+                if let Some(mut machine) = machines.iter_mut().find(|m| m.cell == next) {
+                    let wanted = machine.recipe.and_then(|id| recipes.get(id))
+                        .and_then(|r| r.inputs.iter().find(|(i, _)| *i == belt_item.item))
+                        .map(|(_, amount)| amount * MACHINE_INPUT_BATCHES)
+                        .unwrap_or(0);
+
+                    if machine.input.count(belt_item.item) < wanted {
+                        machine.input.add(belt_item.item, 1);
+                        occupied.remove(&belt_item.cell);
+                        moved.insert(entity);
+                        commands.entity(entity).despawn();
+                        changed = true;
+                        break;
+                    }
+                    continue;
+                }
+
+                let Some(next_belt) = index.cells.get(&next).and_then(|e| belts.get(*e).ok()) else { continue; };
+                if occupied.contains_key(&next) { continue; }
+
+                // mergers let the side that waited go first
+                if next_belt.kind == BeltKind::Merger && pass == 0 && next_belt.last_input == Some(facing) { continue; }
+
+                destination = Some((next, facing));
+                break;
+            }
+
+            let Some((next, facing)) = destination else { continue; };
+
+            if let Ok(mut belt) = belts.get_mut(*belt_entity) && belt.kind == BeltKind::Splitter {
+                belt.toggle = facing == belt.facing.counter_clockwise();
+            }
+            if let Some(mut next_belt) = index.cells.get(&next).and_then(|e| belts.get_mut(*e).ok()) && next_belt.kind == BeltKind::Merger {
+                next_belt.last_input = Some(facing);
+            }
+
+            occupied.remove(&belt_item.cell);
+            occupied.insert(next, entity);
+            moved.insert(entity);
+
+            belt_item.cell = next;
+            transform.translation = Vec3::new(next.x as f32 * TILESIZE as f32, next.y as f32 * TILESIZE as f32, BELT_ITEM_Z_AXIS);
+            changed = true;
+        }
+
+        pass += 1;
+        if !changed { break; }
+    }
+}
+
+fn emit_machine_outputs(
+    mut commands: Commands,
+    clock: Res<BeltClock>,
+    index: Res<BeltIndex>,
+    item_registry: Res<ItemRegistry>,
+    belts: Query<&Belt>,
+    items: Query<&BeltItem>,
+    mut machines: Query<&mut Machine>,
+) {
+    if !clock.0.just_finished() { return; }
+
+    let occupied: HashSet<UVec3> = items.iter().map(|i| i.cell).collect();
+
+    for mut machine in &mut machines {
+        if machine.output.is_empty() { continue; }
+        let cell = machine.cell;
+
+        let port = Facing::ALL.iter().filter_map(|facing| {
+            let next = step(cell, *facing)?;
+            let belt = belts.get(*index.cells.get(&next)?).ok()?;
+            (belt.kind != BeltKind::Splitter && belt.facing == *facing && !occupied.contains(&next)).then_some(next)
+        }).next();
+
+        let Some(port) = port else { continue; };
+        let item = machine.output.remove(0);
+
+        commands.spawn((
+            BeltItem { item, cell: port },
+            Level(port.z),
+            Transform::from_xyz(port.x as f32 * TILESIZE as f32, port.y as f32 * TILESIZE as f32, BELT_ITEM_Z_AXIS),
+            Sprite { color: item_registry.get(item).icon, custom_size: Some(Vec2::new(6.0, 6.0)), ..default() }
+        ));
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::{platform::collections::HashSet, prelude::*};
+use bevy_northstar::prelude::AgentPos;
+
+use crate::{light::LightMap, needs::Needs, water::WaterMap, world::{is_open, Chunk, WorldGrid}};
+
+// explored cells stay known, visible cells are the ones a worker can see right now
+#[derive(Resource)]
+pub struct FogOfWar {
+    pub explored: HashSet<IVec3>,
+    pub visible: HashSet<IVec3>
+}
+
+impl FogOfWar {
+    pub fn is_explored(&self, cell: UVec3) -> bool {
+        self.explored.contains(&cell.as_ivec3())
+    }
+}
+
+#[derive(Resource)]
+struct FogClock(Timer);
+
+pub struct Fog;
+
+impl Plugin for Fog {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FogOfWar { explored: HashSet::new(), visible: HashSet::new() });
+        app.insert_resource(FogClock(Timer::from_seconds(FOG_UPDATE_SECS, TimerMode::Repeating)));
+
+        app.add_systems(Update, (update_sight, tint_fogged_tiles).chain());
+    }
+}
+
+const SIGHT_RADIUS: i32 = 7;
+const FOG_UPDATE_SECS: f32 = 0.25;
+const REMEMBERED_BRIGHTNESS: f32 = 0.4;
+pub const UNEXPLORED_COLOR: Color = Color::srgb(0.02, 0.02, 0.03);
+const DARKEST_BRIGHTNESS: f32 = 0.12; // pitch dark tiles in sight are still faintly drawn
+const WATER_COLOR: Color = Color::srgb(0.1, 0.3, 0.85);
+const ROCK_BRIGHTNESS: f32 = 0.45;
+
+// bresenham from the eye to the cell on the same level, every cell in between has to be open.
+// the cell itself can be rock, walls are seen from the cave side
+fn line_of_sight(grid: &WorldGrid, from: IVec3, to: IVec3) -> bool {
+    let delta = IVec2::new((to.x - from.x).abs(), -(to.y - from.y).abs());
+    let step = IVec2::new((to.x - from.x).signum(), (to.y - from.y).signum());
+    let mut error = delta.x + delta.y;
+    let mut cell = from;
+
+    while cell != to {
+        if cell != from && !is_open(grid, cell) { return false; }
+
+        let doubled = 2 * error;
+        if doubled >= delta.y { error += delta.y; cell.x += step.x; }
+        if doubled <= delta.x { error += delta.x; cell.y += step.y; }
+    }
+    true
+}
+
+fn update_sight(
+    time: Res<Time>,
+    mut clock: ResMut<FogClock>,
+    mut fog: ResMut<FogOfWar>,
+    grid: Single<&WorldGrid>,
+    workers: Query<&AgentPos, With<Needs>>,
+) {
+    if !clock.0.tick(time.delta()).just_finished() { return; }
+    let grid = grid.into_inner();
+
+    let mut visible = HashSet::new();
+    for agent_pos in &workers {
+        let eye = agent_pos.0.as_ivec3();
+
+        for x in -SIGHT_RADIUS..=SIGHT_RADIUS {
+            for y in -SIGHT_RADIUS..=SIGHT_RADIUS {
+                if x * x + y * y > SIGHT_RADIUS * SIGHT_RADIUS { continue; }
+                let cell = eye + IVec3::new(x, y, 0);
+                if cell.cmplt(IVec3::ZERO).any() || grid.nav(cell.as_uvec3()).is_none() { continue; }
+                if line_of_sight(grid, eye, cell) { visible.insert(cell); }
+            }
+        }
+    }
+
+    if visible == fog.visible { return; }
+    fog.explored.extend(visible.iter().copied());
+    fog.visible = visible;
+}
+
+fn scaled(color: Color, brightness: f32) -> Color {
+    let color = color.to_srgba();
+    Color::srgb(color.red * brightness, color.green * brightness, color.blue * brightness)
+}
+
+// how a tile with the given base color is drawn right now. tiles in sight show the water on them
+// and are as bright as the light on them. solid rock under the surface is darker than the dug out
+// floor. the chunk meshes and the zoomed out regions both draw with this
+pub fn tile_tint(base: Color, cell: IVec3, grid: &WorldGrid, fog: &FogOfWar, light: &LightMap, water: &WaterMap) -> Color {
+    let grid_cell = cell.as_uvec3();
+    let rock = cell.z > 0 && !is_open(grid, cell);
+    let color = if rock { scaled(base, ROCK_BRIGHTNESS) } else { base };
+
+    if fog.visible.contains(&cell) {
+        let color = color.mix(&WATER_COLOR, water.level(grid_cell) * 0.8);
+        scaled(color, light.level(grid_cell).max(DARKEST_BRIGHTNESS))
+    } else if fog.explored.contains(&cell) {
+        scaled(color, REMEMBERED_BRIGHTNESS)
+    } else {
+        UNEXPLORED_COLOR
+    }
+}
+
+// a chunk is only touched when one of its tiles comes out different, so its mesh isn't rebuilt
+// for nothing
+fn tint_fogged_tiles(
+    fog: Res<FogOfWar>,
+    light: Res<LightMap>,
+    water: Res<WaterMap>,
+    grid: Single<Ref<WorldGrid>>,
+    redrawn: Query<(), Changed<Chunk>>,
+    mut chunks: Query<&mut Chunk>,
+) {
+    let grid = grid.into_inner();
+    if !fog.is_changed() && !light.is_changed() && !water.is_changed() && !grid.is_changed() && redrawn.is_empty() { return; }
+
+    for mut chunk in &mut chunks {
+        let tints: Vec<Color> = chunk.tiles.iter()
+            .map(|tile| tile_tint(tile.color, tile.cell, &grid, &fog, &light, &water))
+            .collect();
+
+        if chunk.tints != tints { chunk.tints = tints; }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::test_grid;
+
+    #[test]
+    fn sees_across_open_ground() {
+        let grid = test_grid(&[]);
+        assert!(line_of_sight(&grid, IVec3::new(1, 1, 0), IVec3::new(8, 4, 0)));
+        assert!(line_of_sight(&grid, IVec3::new(8, 4, 0), IVec3::new(1, 1, 0)));
+    }
+
+    #[test]
+    fn rock_in_between_blocks_sight() {
+        let grid = test_grid(&[IVec3::new(4, 1, 0)]);
+        assert!(!line_of_sight(&grid, IVec3::new(1, 1, 0), IVec3::new(8, 1, 0)));
+        assert!(line_of_sight(&grid, IVec3::new(1, 3, 0), IVec3::new(8, 3, 0)));
+    }
+
+    #[test]
+    fn the_wall_itself_is_seen() {
+        let grid = test_grid(&[IVec3::new(5, 5, 0)]);
+        assert!(line_of_sight(&grid, IVec3::new(2, 5, 0), IVec3::new(5, 5, 0)));
+    }
+
+    #[test]
+    fn rock_on_another_level_is_ignored() {
+        let grid = test_grid(&[IVec3::new(4, 1, 1)]);
+        assert!(line_of_sight(&grid, IVec3::new(1, 1, 0), IVec3::new(8, 1, 0)));
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs;
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{belt::BeltKind, light::LightKind, machine::MachineKind, power::PowerKind, support::SupportKind, terrain::TerrainKind};
+
+const ITEMS_PATH: &str = "assets/data/items.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemCategory {
+    Unit,
+    Building,
+    Resource,
+    Material,
+    Part,
+    Tool
+}
+
+// what gets spawned in the world when the item is used from the action bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeable {
+    Worker,
+    Machine(MachineKind),
+    Belt(BeltKind),
+    Power(PowerKind),
+    Light(LightKind),
+    Support(SupportKind),
+    Pump,
+    Paving(TerrainKind),
+    Stairs
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemDef {
+    pub key: String,
+    pub name: String,
+    pub icon: Color,
+    pub stack_size: u32,
+    pub category: ItemCategory,
+    pub placeable: Option<Placeable>
+}
+
+#[derive(Debug, Resource)]
+pub struct ItemRegistry { pub items: Vec<ItemDef> }
+
+impl ItemRegistry {
+    pub fn get(&self, id: ItemId) -> &ItemDef {
+        &self.items[id.0]
+    }
+
+    pub fn by_key(&self, key: &str) -> Option<ItemId> {
+        self.items.iter().position(|i| i.key == key).map(ItemId)
+    }
+}
+
+// plain resources with the given keys for tests, in that order
+#[cfg(test)]
+pub fn test_registry(keys: &[&str], stack_size: u32) -> ItemRegistry {
+    let items = keys.iter().map(|key| ItemDef {
+        key: key.to_string(),
+        name: key.to_string(),
+        icon: Color::WHITE,
+        stack_size,
+        category: ItemCategory::Resource,
+        placeable: None
+    }).collect();
+    ItemRegistry { items }
+}
+
+// loose amounts of items, used by the stockpile and machine buffers
+#[derive(Debug, Default, Clone)]
+pub struct ItemCounts(pub HashMap<ItemId, u32>);
+
+impl ItemCounts {
+    pub fn count(&self, item: ItemId) -> u32 {
+        self.0.get(&item).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, item: ItemId, amount: u32) {
+        *self.0.entry(item).or_insert(0) += amount;
+    }
+
+    pub fn has_all(&self, items: &[(ItemId, u32)]) -> bool {
+        items.iter().all(|(item, amount)| self.count(*item) >= *amount)
+    }
+
+    pub fn take_all(&mut self, items: &[(ItemId, u32)]) {
+        for (item, amount) in items {
+            if let Some(total) = self.0.get_mut(item) { *total = total.saturating_sub(*amount); }
+        }
+    }
+
+    pub fn add_all(&mut self, items: &[(ItemId, u32)]) {
+        for (item, amount) in items { self.add(*item, *amount); }
+    }
+}
+
+pub struct Items;
+
+impl Plugin for Items {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ItemRegistry { items: Vec::new() });
+        app.add_systems(PreStartup, load_items);
+    }
+}
+
+fn load_items(mut registry: ResMut<ItemRegistry>) {
+    let Ok(file) = fs::read_to_string(ITEMS_PATH) else {
+        warn!("could not read {}", ITEMS_PATH);
+        return;
+    };
+
+    for (line_number, line) in file.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+
+        match parse_item(line) {
+            Some(item) => registry.items.push(item),
+            None => warn!("{}:{} invalid item: {}", ITEMS_PATH, line_number + 1, line)
+        }
+    }
+}
+
+// key | name | icon | stack size | category | placeable as
+fn parse_item(line: &str) -> Option<ItemDef> {
+    let fields: Vec<&str> = line.split('|').map(|f| f.trim()).collect();
+    if fields.len() != 6 { return None; }
+
+    let rgb: Vec<f32> = fields[2].split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()?;
+    if rgb.len() != 3 { return None; }
+
+    let category = match fields[4] {
+        "unit" => ItemCategory::Unit,
+        "building" => ItemCategory::Building,
+        "resource" => ItemCategory::Resource,
+        "material" => ItemCategory::Material,
+        "part" => ItemCategory::Part,
+        "tool" => ItemCategory::Tool,
+        _ => return None
+    };
+
+    let placeable = match fields[5] {
+        "-" => None,
+        "worker" => Some(Placeable::Worker),
+        "belt" => Some(Placeable::Belt(BeltKind::Straight)),
+        "splitter" => Some(Placeable::Belt(BeltKind::Splitter)),
+        "merger" => Some(Placeable::Belt(BeltKind::Merger)),
+        "coal_burner" => Some(Placeable::Power(PowerKind::CoalBurner)),
+        "water_wheel" => Some(Placeable::Power(PowerKind::WaterWheel)),
+        "power_pole" => Some(Placeable::Power(PowerKind::Pole)),
+        "torch" => Some(Placeable::Light(LightKind::Torch)),
+        "lamp" => Some(Placeable::Light(LightKind::Lamp)),
+        "pillar" => Some(Placeable::Support(SupportKind::Pillar)),
+        "beam" => Some(Placeable::Support(SupportKind::Beam)),
+        "pump" => Some(Placeable::Pump),
+        "road" => Some(Placeable::Paving(TerrainKind::Road)),
+        "floor" => Some(Placeable::Paving(TerrainKind::Floor)),
+        "stairs" => Some(Placeable::Stairs),
+        machine => Some(Placeable::Machine(MachineKind::from_name(machine)?))
+    };
+
+    Some(ItemDef {
+        key: fields[0].to_string(),
+        name: fields[1].to_string(),
+        icon: Color::srgb(rgb[0], rgb[1], rgb[2]),
+        stack_size: fields[3].parse().ok()?,
+        category,
+        placeable
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_full_line() {
+        let item = parse_item("copper_ore | Copper ore | 0.62,0.42,0.3 | 50 | resource | -").unwrap();
+
+        assert_eq!(item.key, "copper_ore");
+        assert_eq!(item.name, "Copper ore");
+        assert_eq!(item.icon, Color::srgb(0.62, 0.42, 0.3));
+        assert_eq!(item.stack_size, 50);
+        assert_eq!(item.category, ItemCategory::Resource);
+        assert_eq!(item.placeable, None);
+    }
+
+    #[test]
+    fn parses_placeables() {
+        let placeable = |field: &str| parse_item(&format!("key | Name | 1,1,1 | 5 | building | {}", field)).unwrap().placeable;
+
+        assert_eq!(placeable("smelter"), Some(Placeable::Machine(MachineKind::Smelter)));
+        assert_eq!(placeable("splitter"), Some(Placeable::Belt(BeltKind::Splitter)));
+        assert_eq!(placeable("stairs"), Some(Placeable::Stairs));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(parse_item("key | Name | 1,1,1 | 5 | building").is_none());
+        assert!(parse_item("key | Name | 1,1 | 5 | building | -").is_none());
+        assert!(parse_item("key | Name | 1,1,1 | many | building | -").is_none());
+        assert!(parse_item("key | Name | 1,1,1 | 5 | furniture | -").is_none());
+        assert!(parse_item("key | Name | 1,1,1 | 5 | building | forge").is_none());
+    }
+
+    #[test]
+    fn counts_add_and_take() {
+        let (ore, coal) = (ItemId(0), ItemId(1));
+        let mut counts = ItemCounts::default();
+        counts.add(ore, 3);
+        counts.add_all(&[(ore, 1), (coal, 2)]);
+
+        assert_eq!(counts.count(ore), 4);
+        assert!(counts.has_all(&[(ore, 4), (coal, 2)]));
+        assert!(!counts.has_all(&[(ore, 5)]));
+
+        counts.take_all(&[(ore, 3), (coal, 5)]);
+        assert_eq!(counts.count(ore), 1);
+        assert_eq!(counts.count(coal), 0); // never goes below nothing
+        assert_eq!(counts.count(ItemId(2)), 0);
+    }
+}
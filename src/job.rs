@@ -0,0 +1,484 @@
+use bevy::prelude::*;
+use bevy_northstar::{nav::Nav, prelude::{AgentPos, NextPos, Path, Pathfind, PathfindingFailed}};
+
+use crate::{belt::{step, Facing}, fog::FogOfWar, item::{ItemId, ItemRegistry}, light::LightMap, machine::Machine, mouse::GridClicked, nav_edit::NavEdits, needs::Needs, occupancy::Occupancy, player::Stockpile, skills::{Skill, Skills}, support::{Support, SupportKind}, terrain::{Paving, TerrainMap}, world::{Level, TILESIZE, WorldGrid}, worldgen::WorldGen};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobKind {
+    Dig,
+    Build,
+    Haul,
+    Operate,
+    Eat,
+    Sleep
+}
+
+impl JobKind {
+    // seconds of work at full productivity
+    pub fn work(&self) -> f32 {
+        match self {
+            JobKind::Dig => 3.0,
+            JobKind::Build => 2.0,
+            JobKind::Haul => 0.5, // for picking up and again for dropping off
+            JobKind::Operate => 20.0, // one shift, the order goes back on the board afterwards
+            JobKind::Eat => 4.0,
+            JobKind::Sleep => 12.0
+        }
+    }
+
+    pub fn skill(&self) -> Option<Skill> {
+        match self {
+            JobKind::Dig => Some(Skill::Mining),
+            JobKind::Build => Some(Skill::Construction),
+            JobKind::Haul => Some(Skill::Hauling),
+            JobKind::Operate => Some(Skill::Operating),
+            JobKind::Eat | JobKind::Sleep => None
+        }
+    }
+
+    // everything that can be posted on the board
+    pub const BOARD: [JobKind; 4] = [JobKind::Dig, JobKind::Build, JobKind::Haul, JobKind::Operate];
+
+    // eating and sleeping are taken care of by the worker itself, never posted on the board
+    pub fn is_need(&self) -> bool {
+        matches!(self, JobKind::Eat | JobKind::Sleep)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobId(pub u32);
+
+// a designated piece of work waiting for, or taken by, a worker
+#[derive(Debug)]
+pub struct JobOrder {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub cell: UVec3,
+    pub assigned: Option<Entity>,
+    pub subject: Option<Entity>, // ground item to haul or machine to operate
+    retry_at: f32, // orders nobody could reach are skipped for a while
+    marker: Option<Entity>
+}
+
+#[derive(Resource)]
+pub struct JobBoard {
+    pub orders: Vec<JobOrder>,
+    next_id: u32
+}
+
+impl JobBoard {
+    pub fn get(&self, id: JobId) -> Option<&JobOrder> {
+        self.orders.iter().find(|o| o.id == id)
+    }
+
+    // takes the order away from its worker and keeps it off the board for a while
+    pub fn defer(&mut self, id: JobId, now: f32) {
+        let Some(order) = self.orders.iter_mut().find(|o| o.id == id) else { return; };
+        order.assigned = None;
+        order.retry_at = now + DEFERRED_RETRY_SECS;
+    }
+
+    fn post(&mut self, kind: JobKind, cell: UVec3, subject: Option<Entity>, marker: Option<Entity>) -> JobId {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+        self.orders.push(JobOrder { id, kind, cell, assigned: None, subject, retry_at: 0.0, marker });
+        id
+    }
+
+    fn finish(&mut self, commands: &mut Commands, id: JobId) {
+        let Some(index) = self.orders.iter().position(|o| o.id == id) else { return; };
+        let order = self.orders.remove(index);
+        if let Some(marker) = order.marker { commands.entity(marker).despawn(); }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct CurrentJob {
+    pub kind: JobKind,
+    pub order: Option<JobId>,
+    pub target: UVec3, // cell being worked on
+    pub stand: UVec3, // cell the worker works from
+    pub progress: f32,
+    pub carrying: bool // hauling, on the way to the stockpile
+}
+
+impl CurrentJob {
+    fn new(kind: JobKind, order: JobId, target: UVec3, stand: UVec3) -> CurrentJob {
+        CurrentJob { kind, order: Some(order), target, stand, progress: 0.0, carrying: false }
+    }
+
+    // eat and sleep happen wherever the worker is standing
+    pub fn in_place(kind: JobKind, cell: UVec3) -> CurrentJob {
+        CurrentJob { kind, order: None, target: cell, stand: cell, progress: 0.0, carrying: false }
+    }
+}
+
+// per worker priority for every board job. 1 is done first, 4 last, 0 never
+#[derive(Debug, Component)]
+pub struct WorkPriorities { levels: [u8; 4] }
+
+impl Default for WorkPriorities {
+    fn default() -> WorkPriorities {
+        WorkPriorities { levels: [DEFAULT_PRIORITY; 4] }
+    }
+}
+
+impl WorkPriorities {
+    fn index(kind: JobKind) -> Option<usize> {
+        JobKind::BOARD.iter().position(|k| *k == kind)
+    }
+
+    pub fn get(&self, kind: JobKind) -> u8 {
+        Self::index(kind).map_or(0, |i| self.levels[i])
+    }
+
+    pub fn set(&mut self, kind: JobKind, priority: u8) {
+        if let Some(i) = Self::index(kind) { self.levels[i] = priority.min(LOWEST_PRIORITY); }
+    }
+
+    // 1 -> 2 -> 3 -> 4 -> off -> 1
+    pub fn cycle(&mut self, kind: JobKind) {
+        let next = (self.get(kind) + 1) % (LOWEST_PRIORITY + 1);
+        self.set(kind, next);
+    }
+}
+
+// dug out material waiting to be hauled to the stockpile
+#[derive(Debug, Component)]
+pub struct GroundItem {
+    pub item: ItemId,
+    pub amount: u32,
+    pub cell: UVec3,
+    carrier: Option<Entity>
+}
+
+pub struct Jobs;
+
+impl Plugin for Jobs {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(JobBoard { orders: Vec::new(), next_id: 0 });
+
+        app.add_systems(Update, (
+            designate_jobs,
+            post_operate_orders,
+            post_construction_orders,
+            release_orphaned_orders,
+            carry_ground_items,
+            assign_jobs,
+            job_unreachable,
+            work_jobs
+        ).chain());
+    }
+}
+
+pub const LOWEST_PRIORITY: u8 = 4;
+pub const DEFAULT_PRIORITY: u8 = 3;
+
+const JOB_MARKER_Z_AXIS: f32 = 3.5;
+const GROUND_ITEM_Z_AXIS: f32 = 3.6;
+const UNREACHABLE_RETRY_SECS: f32 = 5.0;
+const DEFERRED_RETRY_SECS: f32 = 10.0;
+const MINING_LEVELS_PER_EXTRA_ORE: u32 = 4;
+
+// picks and the tier of rock they can dig, the best one in the stockpile counts for everybody
+const PICKS: [(&str, u8); 2] = [("copper_pick", 1), ("iron_pick", 2)];
+
+pub fn pick_tier(stockpile: &Stockpile, items: &ItemRegistry) -> u8 {
+    PICKS.iter()
+        .filter(|(key, _)| items.by_key(key).is_some_and(|pick| stockpile.items.count(pick) > 0))
+        .map(|(_, tier)| *tier)
+        .max()
+        .unwrap_or(0)
+}
+
+// stops whatever path the agent was following
+pub fn stop_agent(commands: &mut Commands, entity: Entity) {
+    commands.entity(entity).remove::<(Pathfind, Path, NextPos)>();
+}
+
+fn cell_to_world(cell: UVec3, z: f32) -> Vec3 {
+    Vec3::new(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, z)
+}
+
+// X on rock orders it dug out, X on open ground orders a wall built, X on a dig or wall order cancels it
+fn designate_jobs(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    input: Res<ButtonInput<KeyCode>>,
+    grid_position: Res<GridClicked>,
+    mut board: ResMut<JobBoard>,
+    fog: Res<FogOfWar>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(KeyCode::KeyX) { return; }
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+
+    if let Some(order) = board.orders.iter().find(|o| o.cell == cell && o.subject.is_none() && matches!(o.kind, JobKind::Dig | JobKind::Build)) {
+        let id = order.id;
+        board.finish(&mut commands, id);
+        return;
+    }
+
+    // buildings aren't dug out and belts aren't walled over
+    if occupancy.is_taken(cell) { return; }
+
+    let kind = match grid.nav(cell) {
+        Some(Nav::Impassable) => JobKind::Dig,
+        Some(Nav::Passable(_)) => JobKind::Build,
+        _ => return
+    };
+
+    let marker = commands.spawn((
+        Level(cell.z),
+        Transform::from_translation(cell_to_world(cell, JOB_MARKER_Z_AXIS)),
+        Sprite {
+            color: if kind == JobKind::Dig { Color::srgba(0.95, 0.6, 0.2, 0.45) } else { Color::srgba(0.4, 0.7, 0.95, 0.45) },
+            custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)),
+            ..default()
+        }
+    )).id();
+
+    board.post(kind, cell, None, Some(marker));
+}
+
+// every machine with a recipe asks for an operator
+fn post_operate_orders(mut board: ResMut<JobBoard>, machines: Query<(Entity, &Machine)>) {
+    for (entity, machine) in &machines {
+        if machine.recipe.is_none() { continue; }
+        if board.orders.iter().any(|o| o.subject == Some(entity)) { continue; }
+        board.post(JobKind::Operate, machine.cell, Some(entity), None);
+    }
+}
+
+// planned supports and paving wait for a builder
+fn post_construction_orders(mut board: ResMut<JobBoard>, supports: Query<(Entity, &Support)>, pavings: Query<(Entity, &Paving)>) {
+    let planned = supports.iter().filter(|(_, s)| !s.built).map(|(e, s)| (e, s.cell))
+        .chain(pavings.iter().filter(|(_, p)| !p.built).map(|(e, p)| (e, p.cell)));
+
+    for (entity, cell) in planned {
+        if board.orders.iter().any(|o| o.subject == Some(entity)) { continue; }
+        board.post(JobKind::Build, cell, Some(entity), None);
+    }
+}
+
+// orders go back on the board when their worker dropped them, for a need, a player command or despawning
+fn release_orphaned_orders(mut board: ResMut<JobBoard>, jobs: Query<&CurrentJob>) {
+    for order in board.orders.iter_mut() {
+        let Some(worker) = order.assigned else { continue; };
+        if !jobs.get(worker).is_ok_and(|job| job.order == Some(order.id)) { order.assigned = None; }
+    }
+}
+
+// carried items follow the hauler, and stay where it stood if the haul gets dropped
+fn carry_ground_items(
+    mut board: ResMut<JobBoard>,
+    jobs: Query<(&AgentPos, &CurrentJob)>,
+    mut items: Query<(Entity, &mut GroundItem, &mut Transform, &mut Level)>,
+) {
+    for (entity, mut ground_item, mut transform, mut level) in &mut items {
+        let Some(carrier) = ground_item.carrier else { continue; };
+        let order = board.orders.iter_mut().find(|o| o.subject == Some(entity));
+
+        match (jobs.get(carrier), order) {
+            (Ok((agent_pos, job)), Some(order)) if job.order == Some(order.id) && job.carrying => {
+                ground_item.cell = agent_pos.0;
+            },
+            (_, order) => {
+                ground_item.carrier = None;
+                if let Some(order) = order { order.cell = ground_item.cell; }
+            }
+        }
+        transform.translation = cell_to_world(ground_item.cell, GROUND_ITEM_Z_AXIS);
+        if level.0 != ground_item.cell.z { level.0 = ground_item.cell.z; }
+    }
+}
+
+// open neighbour of the target closest to the worker
+fn stand_cell(grid: &WorldGrid, kind: JobKind, target: UVec3, from: UVec3) -> Option<UVec3> {
+    if kind == JobKind::Haul { return Some(target); }
+
+    Facing::ALL.iter()
+        .filter_map(|facing| step(target, *facing))
+        .filter(|cell| matches!(grid.nav(*cell), Some(Nav::Passable(_))))
+        .min_by_key(|cell| cell.x.abs_diff(from.x) + cell.y.abs_diff(from.y))
+}
+
+// work is handed out one priority tier at a time, so every worker's priority 1 jobs are taken before
+// anyone's priority 2. within a tier an order goes to the most skilled idle worker, the closest on a tie.
+// rock too hard for the colony's picks waits on the board
+fn assign_jobs(
+    mut commands: Commands,
+    time: Res<Time>,
+    grid: Single<&WorldGrid>,
+    items: Res<ItemRegistry>,
+    stockpile: Res<Stockpile>,
+    world_gen: Res<WorldGen>,
+    terrain: Res<TerrainMap>,
+    mut board: ResMut<JobBoard>,
+    workers: Query<(Entity, &AgentPos, Option<&Pathfind>, Option<&Skills>, Option<&WorkPriorities>), (With<Needs>, Without<CurrentJob>)>,
+) {
+    let grid = grid.into_inner();
+    let now = time.elapsed_secs();
+    let picks = pick_tier(&stockpile, &items);
+
+    // still walking somewhere the player sent it
+    let mut idle: Vec<(Entity, UVec3, Option<&Skills>, Option<&WorkPriorities>)> = workers.iter()
+        .filter(|(_, agent_pos, pathfind, _, _)| pathfind.is_none_or(|p| p.goal == agent_pos.0))
+        .map(|(entity, agent_pos, _, skills, priorities)| (entity, agent_pos.0, skills, priorities))
+        .collect();
+
+    for tier in 1..=LOWEST_PRIORITY {
+        for order in board.orders.iter_mut() {
+            if idle.is_empty() { return; }
+            if order.assigned.is_some() || order.retry_at > now { continue; }
+            let natural = order.kind == JobKind::Dig && terrain.is_natural_rock(order.cell);
+            if natural && world_gen.value.rock(order.cell.as_ivec3()).tier() > picks { continue; }
+
+            let level = |skills: Option<&Skills>| order.kind.skill().zip(skills).map_or(0, |(skill, s)| s.level(skill));
+            let distance = |from: UVec3| order.cell.x.abs_diff(from.x) + order.cell.y.abs_diff(from.y);
+
+            let Some((index, stand)) = idle.iter().enumerate()
+                .filter(|(_, (_, _, _, priorities))| priorities.map_or(DEFAULT_PRIORITY, |p| p.get(order.kind)) == tier)
+                .filter_map(|(i, (_, from, _, _))| stand_cell(grid, order.kind, order.cell, *from).map(|stand| (i, stand)))
+                .max_by(|(a, _), (b, _)| {
+                    level(idle[*a].2).cmp(&level(idle[*b].2))
+                        .then(distance(idle[*b].1).cmp(&distance(idle[*a].1)))
+                })
+            else { continue; };
+
+            let (entity, _, _, _) = idle.swap_remove(index);
+            order.assigned = Some(entity);
+
+            commands.entity(entity).insert((
+                CurrentJob::new(order.kind, order.id, order.cell, stand),
+                Pathfind::new(stand)
+            ));
+        }
+    }
+}
+
+fn job_unreachable(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut board: ResMut<JobBoard>,
+    failed: Query<(Entity, &CurrentJob), Added<PathfindingFailed>>,
+) {
+    for (entity, job) in &failed {
+        if let Some(order) = job.order.and_then(|id| board.orders.iter_mut().find(|o| o.id == id)) {
+            order.assigned = None;
+            order.retry_at = time.elapsed_secs() + UNREACHABLE_RETRY_SECS;
+        }
+        // the failed goal is dropped too, or the worker never counts as idle again
+        stop_agent(&mut commands, entity);
+        commands.entity(entity).remove::<(CurrentJob, PathfindingFailed)>();
+    }
+}
+
+// board jobs, sped up by skill and slowed down by unmet needs. working a job trains its skill
+fn work_jobs(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut edits: ResMut<NavEdits>,
+    items: Res<ItemRegistry>,
+    mut board: ResMut<JobBoard>,
+    mut stockpile: ResMut<Stockpile>,
+    mut light: ResMut<LightMap>,
+    mut workers: Query<(Entity, &AgentPos, &mut CurrentJob, Option<&Needs>, Option<&mut Skills>), Without<NextPos>>,
+    mut ground_items: Query<&mut GroundItem>,
+    mut machines: Query<&mut Machine>,
+    mut supports: Query<&mut Support>,
+    mut pavings: Query<&mut Paving>,
+    mut terrain: ResMut<TerrainMap>,
+    world_gen: Res<WorldGen>,
+    agents: Query<&AgentPos>,
+) {
+    for (entity, agent_pos, mut job, needs, mut skills) in &mut workers {
+        if job.kind.is_need() || agent_pos.0 != job.stand { continue; }
+        let Some(order_id) = job.order else { continue; };
+        let Some(order) = board.get(order_id) else { commands.entity(entity).remove::<CurrentJob>(); continue; };
+        let subject = order.subject;
+
+        let skill = job.kind.skill();
+        let speed = skill.zip(skills.as_deref()).map_or(1.0, |(skill, s)| s.speed(skill));
+        // working in the dark is slow
+        let productivity = needs.map_or(1.0, |n| n.productivity()) * light.work_speed(agent_pos.0);
+        let natural = job.kind == JobKind::Dig && terrain.is_natural_rock(job.target);
+        let hardness = if natural { world_gen.value.rock(job.target.as_ivec3()).hardness() } else { 1.0 };
+
+        job.progress += time.delta_secs() * productivity * speed / hardness;
+        if let (Some(skill), Some(skills)) = (skill, skills.as_mut()) { skills.gain(skill, time.delta_secs()); }
+
+        if job.kind == JobKind::Operate && let Some(mut machine) = subject.and_then(|m| machines.get_mut(m).ok()) {
+            machine.operator = Some(speed * productivity);
+        }
+
+        if job.progress < job.kind.work() { continue; }
+
+        // walls wait until nobody stands in the way
+        let paving = subject.is_some_and(|s| pavings.contains(s));
+        if job.kind == JobKind::Build && !paving && agents.iter().any(|a| a.0 == job.target) { continue; }
+
+        match job.kind {
+            JobKind::Dig => {
+                // only cave rock leaves a roof behind, a wall dug away on the surface is open sky again
+                edits.set(job.target, terrain.nav(job.target));
+                if natural { light.roof(job.target); }
+                terrain.dig(job.target);
+
+                if let Some(ore) = world_gen.value.ore(job.target.as_ivec3()).filter(|_| natural).and_then(|key| items.by_key(key)) {
+                    let mining = skills.as_deref().map_or(0, |s| s.level(Skill::Mining));
+                    let ground_item = commands.spawn((
+                        GroundItem { item: ore, amount: 1 + mining / MINING_LEVELS_PER_EXTRA_ORE, cell: job.target, carrier: None },
+                        Level(job.target.z),
+                        Transform::from_translation(cell_to_world(job.target, GROUND_ITEM_Z_AXIS)),
+                        Sprite { color: items.get(ore).icon, custom_size: Some(Vec2::new(5.0, 5.0)), ..default() }
+                    )).id();
+                    board.post(JobKind::Haul, job.target, Some(ground_item), None);
+                }
+            },
+            JobKind::Build => {
+                // a planned support or paving, or a wall when nothing was planned. beams stay walkable
+                if let Some(mut paving) = subject.and_then(|s| pavings.get_mut(s).ok()) {
+                    paving.built = true;
+                    terrain.set(job.target, paving.kind);
+                    edits.set(job.target, terrain.nav(job.target));
+                } else {
+                    let support = subject.and_then(|s| supports.get_mut(s).ok());
+                    let blocks = support.as_ref().is_none_or(|s| s.kind == SupportKind::Pillar);
+                    if let Some(mut support) = support { support.built = true; }
+                    if blocks { edits.set(job.target, Nav::Impassable); }
+                }
+            },
+            JobKind::Haul => {
+                let Some(mut ground_item) = subject.and_then(|i| ground_items.get_mut(i).ok()) else {
+                    board.finish(&mut commands, order_id);
+                    commands.entity(entity).remove::<CurrentJob>();
+                    continue;
+                };
+
+                if !job.carrying {
+                    // picked up, off to the stockpile
+                    ground_item.carrier = Some(entity);
+                    job.carrying = true;
+                    job.progress = 0.0;
+                    job.stand = stockpile.cell;
+                    commands.entity(entity).insert(Pathfind::new(stockpile.cell));
+                    continue;
+                }
+
+                stockpile.items.add(ground_item.item, ground_item.amount);
+                if let Some(ground_item) = subject { commands.entity(ground_item).despawn(); }
+            },
+            JobKind::Operate => {
+                // end of the shift, someone else may pick it up
+                commands.entity(entity).remove::<CurrentJob>();
+                continue;
+            },
+            JobKind::Eat | JobKind::Sleep => {}
+        }
+
+        board.finish(&mut commands, order_id);
+        commands.entity(entity).remove::<CurrentJob>();
+    }
+}
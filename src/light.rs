@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+use bevy::{platform::collections::{HashMap, HashSet}, prelude::*};
+use bevy_northstar::{nav::Nav, prelude::AgentPos};
+
+use crate::{belt::{step, Facing}, fog::FogOfWar, item::{ItemRegistry, Placeable}, mouse::GridClicked, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory}, power::{PowerConsumer, PowerNode}, world::{is_open, Level, TILESIZE, WorldGrid}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightKind {
+    Torch,
+    Lamp
+}
+
+impl LightKind {
+    fn brightness(&self) -> f32 {
+        match self {
+            LightKind::Torch => 0.9,
+            LightKind::Lamp => 1.0
+        }
+    }
+
+    fn radius(&self) -> u32 {
+        match self {
+            LightKind::Torch => 6,
+            LightKind::Lamp => 10
+        }
+    }
+}
+
+// a light placed in the world, lamps only shine as bright as their power network allows
+#[derive(Debug, Component)]
+pub struct LightSource { pub kind: LightKind, pub cell: UVec3 }
+
+// the small light every worker brings along
+#[derive(Debug, Component)]
+pub struct CarriedLight { pub brightness: f32, pub radius: u32 }
+
+impl Default for CarriedLight {
+    fn default() -> Self {
+        CarriedLight { brightness: 0.5, radius: 3 }
+    }
+}
+
+// roofed cells are the ones dug out of rock and everything under the surface, they only get light
+// from sources or spilling in from the open sky. everything else is lit by daylight
+#[derive(Resource, Default)]
+pub struct LightMap {
+    pub roofed: HashSet<IVec3>,
+    levels: HashMap<IVec3, f32>
+}
+
+impl LightMap {
+    // 0 is pitch dark, 1 is daylight
+    pub fn level(&self, cell: UVec3) -> f32 {
+        let cell = cell.as_ivec3();
+        if !self.is_roofed(cell) { return 1.0; }
+        self.levels.get(&cell).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_roofed(&self, cell: IVec3) -> bool {
+        cell.z > 0 || self.roofed.contains(&cell)
+    }
+
+    pub fn roof(&mut self, cell: UVec3) {
+        self.roofed.insert(cell.as_ivec3());
+    }
+
+    // how fast work goes at the cell compared to daylight
+    pub fn work_speed(&self, cell: UVec3) -> f32 {
+        DARK_WORK_SPEED + (1.0 - DARK_WORK_SPEED) * self.level(cell)
+    }
+}
+
+#[derive(Resource)]
+struct LightClock(Timer);
+
+pub struct Lighting;
+
+impl Plugin for Lighting {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LightMap { roofed: HashSet::new(), levels: HashMap::new() });
+        app.insert_resource(LightClock(Timer::from_seconds(LIGHT_UPDATE_SECS, TimerMode::Repeating)));
+
+        app.add_systems(Update, (place_light, update_light));
+    }
+}
+
+const LIGHT_Z_AXIS: f32 = 3.0;
+const LIGHT_UPDATE_SECS: f32 = 0.25;
+const LAMP_DRAW: f32 = 5.0;
+const SKY_FALLOFF: f32 = 0.25; // per cell away from the cave mouth
+const DARK_WORK_SPEED: f32 = 0.3;
+
+fn place_light(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    items: Res<ItemRegistry>,
+    fog: Res<FogOfWar>,
+    mut inventory: ResMut<PlayerInventory>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    let Some(Placeable::Light(kind)) = items.get(item).placeable else { return; };
+
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+
+    // lights stay walkable
+    if !matches!(grid.nav(cell), Some(Nav::Passable(_))) { return; }
+    if occupancy.is_taken(cell) { return; }
+    if !inventory.take_one(item) { return; }
+
+    let mut light = commands.spawn((
+        Name::new(format!("{:?}", kind)),
+        LightSource { kind, cell },
+        Level(cell.z),
+        Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, LIGHT_Z_AXIS),
+        Sprite { color: items.get(item).icon, custom_size: Some(Vec2::new(4.0, 4.0)), ..default() }
+    ));
+    if kind == LightKind::Lamp {
+        light.insert((PowerNode { cell, reach: 1, network: None }, PowerConsumer::new(LAMP_DRAW)));
+    }
+}
+
+// spreads light out from the seeds one cell at a time, losing falloff per cell.
+// rock takes the light but doesn't pass it on
+fn spread(grid: &WorldGrid, seeds: Vec<(UVec3, f32)>, falloff: f32) -> HashMap<IVec3, f32> {
+    let mut levels: HashMap<IVec3, f32> = HashMap::new();
+    let mut queue: VecDeque<(UVec3, f32)> = VecDeque::new();
+    for (cell, level) in seeds {
+        levels.insert(cell.as_ivec3(), level);
+        queue.push_back((cell, level));
+    }
+
+    while let Some((cell, level)) = queue.pop_front() {
+        if !is_open(grid, cell.as_ivec3()) { continue; }
+        let next_level = level - falloff;
+        if next_level <= 0.0 { continue; }
+
+        for facing in Facing::ALL {
+            let Some(next) = step(cell, facing) else { continue; };
+            if grid.nav(next).is_none() { continue; }
+
+            let key = next.as_ivec3();
+            if levels.get(&key).is_some_and(|l| *l >= next_level) { continue; }
+            levels.insert(key, next_level);
+            queue.push_back((next, next_level));
+        }
+    }
+    levels
+}
+
+// overlapping lights don't add up, the brightest one wins
+fn merge(levels: &mut HashMap<IVec3, f32>, other: HashMap<IVec3, f32>) {
+    for (cell, level) in other {
+        let current = levels.entry(cell).or_insert(0.0);
+        *current = current.max(level);
+    }
+}
+
+fn update_light(
+    time: Res<Time>,
+    mut clock: ResMut<LightClock>,
+    mut light_map: ResMut<LightMap>,
+    grid: Single<&WorldGrid>,
+    sources: Query<(&LightSource, Option<&PowerConsumer>)>,
+    carried: Query<(&AgentPos, &CarriedLight)>,
+) {
+    if !clock.0.tick(time.delta()).just_finished() { return; }
+    let grid = grid.into_inner();
+
+    // daylight coming in through the cave mouths on the surface
+    let mut sky = Vec::new();
+    for cell in light_map.roofed.iter().filter(|c| c.z == 0) {
+        let cell = cell.as_uvec3();
+        let open_sky = Facing::ALL.iter().filter_map(|f| step(cell, *f)).any(|n| {
+            is_open(grid, n.as_ivec3()) && !light_map.roofed.contains(&n.as_ivec3())
+        });
+        if open_sky { sky.push((cell, 1.0 - SKY_FALLOFF)); }
+    }
+    let mut levels = spread(grid, sky, SKY_FALLOFF);
+
+    for (source, power) in &sources {
+        let brightness = source.kind.brightness() * power.map_or(1.0, |p| p.satisfaction);
+        if brightness <= 0.0 { continue; }
+        merge(&mut levels, spread(grid, vec![(source.cell, brightness)], brightness / source.kind.radius() as f32));
+    }
+
+    for (agent_pos, light) in &carried {
+        merge(&mut levels, spread(grid, vec![(agent_pos.0, light.brightness)], light.brightness / light.radius as f32));
+    }
+
+    // outside the caves it is daylight anyway
+    levels.retain(|cell, _| light_map.is_roofed(*cell));
+    if levels == light_map.levels { return; }
+    light_map.levels = levels;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::test_grid;
+
+    #[test]
+    fn light_falls_off_per_cell() {
+        let grid = test_grid(&[]);
+        let levels = spread(&grid, vec![(UVec3::new(2, 2, 0), 1.0)], 0.25);
+
+        assert_eq!(levels[&IVec3::new(2, 2, 0)], 1.0);
+        assert_eq!(levels[&IVec3::new(3, 2, 0)], 0.75);
+        assert_eq!(levels[&IVec3::new(4, 2, 0)], 0.5);
+        assert!(!levels.contains_key(&IVec3::new(6, 2, 0)));
+    }
+
+    #[test]
+    fn rock_takes_light_but_stops_it() {
+        let wall: Vec<IVec3> = (0..12).map(|y| IVec3::new(3, y, 0)).collect();
+        let grid = test_grid(&wall);
+        let levels = spread(&grid, vec![(UVec3::new(1, 5, 0), 1.0)], 0.1);
+
+        assert!((levels[&IVec3::new(3, 5, 0)] - 0.8).abs() < 1e-5);
+        assert!(!levels.contains_key(&IVec3::new(4, 5, 0)));
+        assert!(!levels.contains_key(&IVec3::new(1, 5, 1)));
+    }
+
+    #[test]
+    fn the_brightest_light_wins() {
+        let mut levels = HashMap::from_iter([(IVec3::ZERO, 0.3), (IVec3::X, 0.9)]);
+        merge(&mut levels, HashMap::from_iter([(IVec3::ZERO, 0.6), (IVec3::X, 0.2)]));
+
+        assert_eq!(levels[&IVec3::ZERO], 0.6);
+        assert_eq!(levels[&IVec3::X], 0.9);
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::{asset::RenderAssetUsages, platform::collections::HashMap, prelude::*, render::render_resource::{Extent3d, TextureDimension, TextureFormat}};
+
+use crate::{fog::{tile_tint, FogOfWar, UNEXPLORED_COLOR}, light::LightMap, water::WaterMap, world::{DesiredChunks, ViewLevel, WorldGrid, CHUNK_SIZE, KEEP_MARGIN, RENDER_MARGIN, TILESIZE}, worldgen::{WorldGen, WorldGenerator}};
+
+// a block of chunks drawn as one small image when zoomed far out, every pixel is a few cells
+// averaged. the generated colors of its cells are kept, so the image can be redrawn with the
+// same tints as the chunk tiles whenever the world changes
+#[derive(Debug, Component)]
+pub struct LodRegion {
+    pub coords: IVec2,
+    level: i32,
+    colors: Vec<Color>
+}
+
+// every lod region by its coords, empty while the chunks are detailed
+#[derive(Resource)]
+pub struct LodIndex { pub regions: HashMap<IVec2, Entity> }
+
+pub struct LevelOfDetail;
+
+impl Plugin for LevelOfDetail {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LodIndex { regions: HashMap::new() });
+        app.add_systems(Update, update_lod_regions);
+    }
+}
+
+const LOD_Z_AXIS: f32 = 1.0;
+const REGION_CHUNKS: i32 = 4;
+const REGION_CELLS: i32 = REGION_CHUNKS * CHUNK_SIZE;
+const CELLS_PER_PIXEL: i32 = 2;
+const REGION_PIXELS: i32 = REGION_CELLS / CELLS_PER_PIXEL;
+
+fn pixel_cell(region: IVec2, pixel: IVec2) -> IVec2 {
+    region * REGION_CELLS + pixel * CELLS_PER_PIXEL
+}
+
+fn pixel_cells(region: IVec2, pixel: IVec2) -> impl Iterator<Item = IVec2> {
+    let first = pixel_cell(region, pixel);
+    (0..CELLS_PER_PIXEL).flat_map(move |cx| (0..CELLS_PER_PIXEL).map(move |cy| first + IVec2::new(cx, cy)))
+}
+
+fn cell_index(region: IVec2, cell: IVec2) -> usize {
+    let local = cell - region * REGION_CELLS;
+    (local.x * REGION_CELLS + local.y) as usize
+}
+
+fn region_colors(generator: &dyn WorldGenerator, region: IVec2, level: i32) -> Vec<Color> {
+    let mut colors = Vec::new();
+    for x in 0..REGION_CELLS {
+        for y in 0..REGION_CELLS {
+            colors.push(generator.tile_color((region * REGION_CELLS + IVec2::new(x, y)).extend(level)));
+        }
+    }
+    colors
+}
+
+// a pixel shows once any of its cells was explored, as the average of its explored cells tinted
+// like their tiles. image rows go top down, the world goes up
+fn draw_region(image: &mut Image, region: &LodRegion, grid: &WorldGrid, fog: &FogOfWar, light: &LightMap, water: &WaterMap) {
+    for x in 0..REGION_PIXELS {
+        for y in 0..REGION_PIXELS {
+            let tints: Vec<LinearRgba> = pixel_cells(region.coords, IVec2::new(x, y))
+                .map(|cell| cell.extend(region.level))
+                .filter(|cell| fog.explored.contains(cell))
+                .map(|cell| tile_tint(region.colors[cell_index(region.coords, cell.truncate())], cell, grid, fog, light, water).to_linear())
+                .collect();
+
+            let color = if tints.is_empty() {
+                UNEXPLORED_COLOR
+            } else {
+                Color::from(tints.iter().fold(LinearRgba::NONE, |sum, tint| sum + *tint) * (1.0 / tints.len() as f32))
+            };
+            let _ = image.set_color_at(x as u32, (REGION_PIXELS - 1 - y) as u32, color);
+        }
+    }
+}
+
+fn update_lod_regions(
+    mut commands: Commands,
+    world_gen: Res<WorldGen>,
+    view: Res<ViewLevel>,
+    fog: Res<FogOfWar>,
+    light: Res<LightMap>,
+    water: Res<WaterMap>,
+    grid: Single<Ref<WorldGrid>>,
+    desired_chunks: Res<DesiredChunks>,
+    mut lod_index: ResMut<LodIndex>,
+    mut images: ResMut<Assets<Image>>,
+    regions: Query<(&LodRegion, &Sprite)>,
+) {
+    // zoomed back in, or another level was picked
+    if desired_chunks.detailed || view.is_changed() {
+        for (_, region) in lod_index.regions.drain() { commands.entity(region).despawn(); }
+        if desired_chunks.detailed { return; }
+    }
+
+    let to_region = |chunk: IVec2| chunk.div_euclid(IVec2::splat(REGION_CHUNKS));
+    let min = to_region(desired_chunks.view.min - IVec2::splat(RENDER_MARGIN)).max(IVec2::ZERO);
+    let max = to_region(desired_chunks.view.max + IVec2::splat(RENDER_MARGIN));
+    let keep = IRect::from_corners(
+        to_region(desired_chunks.view.min - IVec2::splat(KEEP_MARGIN * REGION_CHUNKS)),
+        to_region(desired_chunks.view.max + IVec2::splat(KEEP_MARGIN * REGION_CHUNKS)),
+    );
+
+    let far: Vec<IVec2> = lod_index.regions.keys().filter(|coords| !keep.contains(**coords)).copied().collect();
+    for coords in far {
+        let Some(region) = lod_index.regions.remove(&coords) else { continue; };
+        commands.entity(region).despawn();
+    }
+
+    let grid = grid.into_inner();
+    if fog.is_changed() || light.is_changed() || water.is_changed() || grid.is_changed() {
+        for (region, sprite) in &regions {
+            let Some(image) = images.get_mut(&sprite.image) else { continue; };
+            draw_region(image, region, &grid, &fog, &light, &water);
+        }
+    }
+
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            let coords = IVec2::new(x, y);
+            if lod_index.regions.contains_key(&coords) { continue; }
+
+            let level = view.0 as i32;
+            let region = LodRegion { coords, level, colors: region_colors(world_gen.value.as_ref(), coords, level) };
+            let mut image = Image::new_fill(
+                Extent3d { width: REGION_PIXELS as u32, height: REGION_PIXELS as u32, depth_or_array_layers: 1 },
+                TextureDimension::D2,
+                &[0, 0, 0, 255],
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::default(),
+            );
+            draw_region(&mut image, &region, &grid, &fog, &light, &water);
+
+            // tiles are centered on their cell, so the region starts half a tile early
+            let size = (REGION_CELLS * TILESIZE) as f32;
+            let corner = (coords * REGION_CELLS * TILESIZE).as_vec2() - TILESIZE as f32 / 2.0;
+            let entity = commands.spawn((
+                Name::new(format!("Lod region {} {}", x, y)),
+                Transform::from_translation((corner + size / 2.0).extend(LOD_Z_AXIS)),
+                Sprite { image: images.add(image), custom_size: Some(Vec2::splat(size)), ..default() },
+                region,
+            )).id();
+            lod_index.regions.insert(coords, entity);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use bevy_northstar::nav::Nav;
+
+use crate::{fog::FogOfWar, item::{ItemCounts, ItemId, ItemRegistry, Placeable}, mouse::GridClicked, nav_edit::NavEdits, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory, Stockpile}, power::{PowerConsumer, PowerNode}, recipe::{RecipeId, RecipeRegistry}, world::{Level, TILESIZE, WorldGrid}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MachineKind {
+    Smelter,
+    Workbench
+}
+
+impl MachineKind {
+    pub fn from_name(name: &str) -> Option<MachineKind> {
+        match name {
+            "smelter" => Some(MachineKind::Smelter),
+            "workbench" => Some(MachineKind::Workbench),
+            _ => None
+        }
+    }
+
+    pub fn power_draw(&self) -> f32 {
+        match self {
+            MachineKind::Smelter => 40.0,
+            MachineKind::Workbench => 15.0
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Machine {
+    pub kind: MachineKind,
+    pub cell: UVec3,
+    pub recipe: Option<RecipeId>,
+    pub progress: Option<f32>, // seconds spent on the current batch, none when idle
+    pub input: ItemCounts, // delivered by belts
+    pub output: Vec<ItemId>, // waiting to be put on an output belt
+    pub output_port: bool, // an adjacent belt leads away from the machine
+    pub operator: Option<f32> // speed boost from the worker operating it this frame
+}
+
+impl Machine {
+    pub fn new(kind: MachineKind, cell: UVec3) -> Machine {
+        Machine { kind, cell, recipe: None, progress: None, input: ItemCounts::default(), output: Vec::new(), output_port: false, operator: None }
+    }
+}
+
+// machine the recipe panel is showing
+#[derive(Resource)]
+pub struct SelectedMachine { pub entity: Option<Entity> }
+
+pub struct Machines;
+
+impl Plugin for Machines {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SelectedMachine { entity: None });
+
+        app.add_systems(Update, (place_machine, machine_selection, run_machines));
+    }
+}
+
+const MACHINE_Z_AXIS: f32 = 3.0;
+const MACHINE_OUTPUT_CAP: usize = 8;
+
+fn place_machine(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    mut edits: ResMut<NavEdits>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    items: Res<ItemRegistry>,
+    mut inventory: ResMut<PlayerInventory>,
+    fog: Res<FogOfWar>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    let Some(Placeable::Machine(kind)) = items.get(item).placeable else { return; };
+
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+
+    // only on open ground, the machine blocks the cell afterwards
+    if !matches!(grid.nav(cell), Some(Nav::Passable(_))) { return; }
+    if occupancy.is_taken(cell) { return; }
+    if !inventory.take_one(item) { return; }
+    edits.set(cell, Nav::Impassable);
+
+    commands.spawn((
+        Name::new(format!("{:?}", kind)),
+        Machine::new(kind, cell),
+        Level(cell.z),
+        PowerNode { cell, reach: 1, network: None },
+        PowerConsumer::new(kind.power_draw()),
+        Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, MACHINE_Z_AXIS),
+        Sprite { color: items.get(item).icon, custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)), ..default() }
+    ));
+}
+
+fn machine_selection(
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    mut selected: ResMut<SelectedMachine>,
+    machines: Query<(Entity, &Machine)>,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    if !object_selected.is_tool() { return; }
+
+    let entity = machines.iter()
+        .find(|(_, m)| m.cell == grid_position.position)
+        .map(|(e, _)| e);
+    // the panel is rebuilt whenever the selection changes
+    if selected.entity != entity { selected.entity = entity; }
+}
+
+// runs the recipe from the belt fed input buffer, or from the stockpile when the belts haven't
+// delivered a full batch. outputs go onto the belts when there is an output port.
+// batches progress at the rate the machine's power network can supply, faster with a skilled operator
+fn run_machines(
+    time: Res<Time>,
+    registry: Res<RecipeRegistry>,
+    mut stockpile: ResMut<Stockpile>,
+    mut machines: Query<(&mut Machine, &PowerConsumer)>,
+) {
+    for (mut machine, power) in &mut machines {
+        let Some(recipe) = machine.recipe.and_then(|id| registry.get(id)) else { continue; };
+
+        match machine.progress {
+            None => {
+                if machine.output.len() >= MACHINE_OUTPUT_CAP { continue; } // output belt is backed up
+                if machine.input.has_all(&recipe.inputs) {
+                    machine.input.take_all(&recipe.inputs);
+                    machine.progress = Some(0.0);
+                } else if stockpile.items.has_all(&recipe.inputs) {
+                    stockpile.items.take_all(&recipe.inputs);
+                    machine.progress = Some(0.0);
+                }
+            },
+            Some(elapsed) => {
+                let operator = machine.operator.take().unwrap_or(1.0);
+                let elapsed = elapsed + time.delta_secs() * power.satisfaction * operator;
+                if elapsed < recipe.duration {
+                    machine.progress = Some(elapsed);
+                    continue;
+                }
+
+                if machine.output_port {
+                    for (item, amount) in &recipe.outputs {
+                        for _ in 0..*amount { machine.output.push(*item); }
+                    }
+                } else {
+                    stockpile.items.add_all(&recipe.outputs);
+                }
+                machine.progress = None;
+            }
+        }
+    }
+}
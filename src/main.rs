@@ -1,13 +1,35 @@
 use bevy::{image::ImageSamplerDescriptor, prelude::*, render::{settings::{Backends, RenderCreation, WgpuSettings}, RenderPlugin}, window::WindowResolution};
 use bevy_northstar::prelude::*;
 
-mod grid;
 mod mouse;
 mod world;
 mod player;
 mod camera;
 mod worker;
 mod ui_game;
+mod item;
+mod recipe;
+mod machine;
+mod ui_machine;
+mod belt;
+mod power;
+mod job;
+mod needs;
+mod skills;
+mod ui_workers;
+mod ui_inspector;
+mod ui_tooltip;
+mod fog;
+mod light;
+mod support;
+mod water;
+mod terrain;
+mod stairs;
+mod nav_edit;
+mod nav_stream;
+mod lod;
+mod worldgen;
+mod occupancy;
 
 // pending *create gamestates
 
@@ -27,8 +49,10 @@ fn main() {
     }));
     
     // pathfinding
-    app.add_plugins(NorthstarPlugin::<CardinalNeighborhood>::default());
-    app.add_plugins(NorthstarDebugPlugin::<CardinalNeighborhood>::default());
+    app.add_plugins(NorthstarPlugin::<world::WorldNeighborhood>::default());
+    app.add_plugins(NorthstarDebugPlugin::<world::WorldNeighborhood>::default());
+    app.add_plugins(nav_edit::NavEditing);
+    app.add_plugins(nav_stream::NavStreaming);
     
     app.add_plugins(mouse::MyMousePlugin);
     app.add_plugins(camera::MyCameraPlugin);
@@ -36,9 +60,28 @@ fn main() {
     app.add_plugins(ui_game::GameUi);
     app.add_plugins(player::Player);
 
-    app.add_plugins(world::MyWorldPlugin);
+    app.add_plugins(world::MyWorldPlugin::from_args(std::env::args()));
+    app.add_plugins(lod::LevelOfDetail);
     app.add_plugins(worker::Worker);
 
+    app.add_plugins(item::Items);
+    app.add_plugins(recipe::Recipes);
+    app.add_plugins(machine::Machines);
+    app.add_plugins(ui_machine::MachineUi);
+    app.add_plugins(belt::Belts);
+    app.add_plugins(power::Power);
+    app.add_plugins(job::Jobs);
+    app.add_plugins(needs::WorkerNeeds);
+    app.add_plugins(ui_workers::WorkersUi);
+    app.add_plugins(ui_inspector::InspectorUi);
+    app.add_plugins(ui_tooltip::TooltipUi);
+    app.add_plugins(fog::Fog);
+    app.add_plugins(light::Lighting);
+    app.add_plugins(support::Supports);
+    app.add_plugins(water::Water);
+    app.add_plugins(terrain::Terrain);
+    app.add_plugins(stairs::Stairways);
+
     app.run();
 }
 
@@ -1,6 +1,6 @@
 use bevy::{prelude::*, window::PrimaryWindow};
 
-use crate::{camera::MainCamera, world::TILESIZE};
+use crate::{camera::MainCamera, world::{ViewLevel, TILESIZE}};
 
 #[derive(Resource, Debug)]
 pub struct MyWorldCoords(pub Vec2);
@@ -75,6 +75,7 @@ fn mouse_pixel_position(
 fn grid_click_coords(
     window: Single<&Window>,
     mut clik_position: ResMut<GridClicked>,
+    view: Res<ViewLevel>,
     camera: Single<(&Camera, &GlobalTransform, &Transform), With<Camera>>
 ) {
     let window = window.into_inner();
@@ -88,7 +89,7 @@ fn grid_click_coords(
             UVec3::new(
                 (cursor_position.x / TILESIZE as f32).round() as u32,
                 (cursor_position.y / TILESIZE as f32).round() as u32,
-                0,
+                view.0,
             )
         });
 
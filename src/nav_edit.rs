@@ -0,0 +1,75 @@
+use bevy::{platform::collections::{HashMap, HashSet}, prelude::*};
+use bevy_northstar::{nav::Nav, prelude::{NextPos, Path, Pathfind}};
+
+use crate::world::{WorldGrid, NAV_CHUNK_SIZE};
+
+// nav changes wait here until the end of the tick, then the grid is rebuilt once for all of them.
+// the last edit to a cell in a tick wins
+#[derive(Resource, Default)]
+pub struct NavEdits {
+    cells: HashMap<IVec3, Nav>,
+    touched: HashSet<UVec2> // chunk columns the colony or the water changed
+}
+
+impl NavEdits {
+    pub fn set(&mut self, cell: UVec3, nav: Nav) {
+        self.touched.insert(nav_chunk(cell).truncate());
+        self.cells.insert(cell.as_ivec3(), nav);
+    }
+
+    // generated data, it can be made again so it doesn't keep the chunk around
+    pub fn stream(&mut self, cell: UVec3, nav: Nav) {
+        self.cells.insert(cell.as_ivec3(), nav);
+    }
+
+    pub fn is_touched(&self, chunk: UVec2) -> bool {
+        self.touched.contains(&chunk)
+    }
+
+    // what the cell is going to be once this tick's edits are in
+    pub fn nav(&self, grid: &WorldGrid, cell: UVec3) -> Option<Nav> {
+        self.cells.get(&cell.as_ivec3()).cloned().or_else(|| grid.nav(cell))
+    }
+}
+
+pub struct NavEditing;
+
+impl Plugin for NavEditing {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavEdits>();
+
+        // after everything in Update had its say
+        app.add_systems(PostUpdate, apply_nav_edits);
+    }
+}
+
+fn nav_chunk(cell: UVec3) -> UVec3 {
+    UVec3::new(cell.x / NAV_CHUNK_SIZE, cell.y / NAV_CHUNK_SIZE, cell.z)
+}
+
+// writes the tick's edits and rebuilds once. the grid only redoes the chunks set_nav touched, so a
+// few edits on a big map stay cheap. anyone whose route runs through a changed chunk looks again
+fn apply_nav_edits(
+    mut commands: Commands,
+    mut edits: ResMut<NavEdits>,
+    grid: Single<&mut WorldGrid>,
+    agents: Query<(Entity, &Pathfind, &Path)>,
+) {
+    if edits.cells.is_empty() { return; }
+    let mut grid = grid.into_inner();
+
+    let mut dirty: HashSet<UVec3> = HashSet::new();
+    for (cell, nav) in edits.cells.drain() {
+        // the grid shrank past it since
+        let cell = cell.as_uvec3();
+        if grid.nav(cell).is_none() { continue; }
+        grid.set_nav(cell, nav);
+        dirty.insert(nav_chunk(cell));
+    }
+    grid.build();
+
+    for (entity, pathfind, path) in &agents {
+        if !path.path().iter().any(|cell| dirty.contains(&nav_chunk(*cell))) { continue; }
+        commands.entity(entity).remove::<(Path, NextPos)>().insert(Pathfind::new(pathfind.goal));
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::{platform::collections::HashSet, prelude::*};
+use bevy_northstar::{nav::Nav, prelude::AgentPos};
+
+use crate::{camera::MainCameraActualPosition, nav_edit::NavEdits, world::{new_grid, WorldGrid, LEVELS, NAV_CHUNK_SIZE, START_CHUNKS, TILESIZE}};
+
+// a column of nav chunks was generated, the same x y on every level
+#[derive(Event)]
+pub struct NavChunkLoaded(pub UVec2);
+
+// a column was dropped again, only columns the colony never changed are paged out
+#[derive(Event)]
+pub struct NavChunkUnloaded(pub UVec2);
+
+// the columns with nav data. the grid reaches from the origin just past the furthest of them in
+// whole chunks, everything in it that isn't loaded is solid rock
+#[derive(Resource)]
+pub struct NavChunks { pub loaded: HashSet<UVec2>, pub size: u32 }
+
+pub struct NavStreaming;
+
+impl Plugin for NavStreaming {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(NavChunks { loaded: HashSet::new(), size: START_CHUNKS });
+        app.add_event::<NavChunkLoaded>();
+        app.add_event::<NavChunkUnloaded>();
+
+        app.add_systems(Update, stream_nav_chunks);
+    }
+}
+
+const LOAD_RADIUS: i32 = 2; // chunks around every worker and the camera
+const UNLOAD_RADIUS: i32 = 4; // further than this and an untouched column is paged out again
+
+// every cell of a column on one level
+pub fn chunk_cells(chunk: UVec2) -> impl Iterator<Item = UVec2> {
+    let origin = chunk * NAV_CHUNK_SIZE;
+    (0..NAV_CHUNK_SIZE).flat_map(move |x| (0..NAV_CHUNK_SIZE).map(move |y| origin + UVec2::new(x, y)))
+}
+
+fn chunk_of(cell: IVec2) -> IVec2 {
+    cell.div_euclid(IVec2::splat(NAV_CHUNK_SIZE as i32))
+}
+
+// the columns within radius of a chunk, the world has an edge at the origin
+fn around(center: IVec2, radius: i32) -> impl Iterator<Item = UVec2> {
+    (-radius..=radius)
+        .flat_map(move |x| (-radius..=radius).map(move |y| center + IVec2::new(x, y)))
+        .filter(|chunk| chunk.cmpge(IVec2::ZERO).all())
+        .map(|chunk| chunk.as_uvec2())
+}
+
+// loads columns around the workers and pages out the ones nobody is near. workers heading past the
+// edge make the grid grow, the camera only loads what it already covers so looking around doesn't
+// make the world any bigger. paths across a seam work like any other path, the grid links
+// neighbouring chunks on every rebuild
+fn stream_nav_chunks(
+    camera: Res<MainCameraActualPosition>,
+    grid: Single<&mut WorldGrid>,
+    mut chunks: ResMut<NavChunks>,
+    mut edits: ResMut<NavEdits>,
+    mut loaded: EventWriter<NavChunkLoaded>,
+    mut unloaded: EventWriter<NavChunkUnloaded>,
+    agents: Query<&AgentPos>,
+) {
+    let camera_chunk = chunk_of((camera.0 / TILESIZE as f32).round().as_ivec2());
+    let workers: Vec<IVec2> = agents.iter().map(|a| chunk_of(a.0.truncate().as_ivec2())).collect();
+
+    let size = chunks.size;
+    let mut wanted: HashSet<UVec2> = workers.iter().flat_map(|center| around(*center, LOAD_RADIUS)).collect();
+    wanted.extend(around(camera_chunk, LOAD_RADIUS).filter(|chunk| chunk.cmplt(UVec2::splat(size)).all()));
+
+    let new: Vec<UVec2> = wanted.into_iter().filter(|chunk| !chunks.loaded.contains(chunk)).collect();
+    let far: Vec<UVec2> = chunks.loaded.iter()
+        .filter(|chunk| !edits.is_touched(**chunk))
+        .filter(|chunk| workers.iter().chain([&camera_chunk]).all(|c| (chunk.as_ivec2() - *c).abs().max_element() > UNLOAD_RADIUS))
+        .copied()
+        .collect();
+    if new.is_empty() && far.is_empty() { return; }
+
+    // the dropped columns go back to rock before anything is carried over, so a column that was
+    // paged out never comes back with its old data
+    for chunk in far.iter() {
+        chunks.loaded.remove(chunk);
+        for cell in chunk_cells(*chunk) { edits.stream(cell.extend(0), Nav::Impassable); }
+    }
+
+    let fitted = fit_size(chunks.size, chunks.loaded.iter().chain(new.iter()));
+    if fitted != chunks.size {
+        resize_grid(&mut grid.into_inner(), fitted, chunks.loaded.iter().copied());
+        chunks.size = fitted;
+    }
+
+    for chunk in far { unloaded.write(NavChunkUnloaded(chunk)); }
+    for chunk in new {
+        chunks.loaded.insert(chunk);
+        loaded.write(NavChunkLoaded(chunk));
+    }
+}
+
+// whole chunks from the origin out past the furthest column, doubling so a worker walking along
+// the edge doesn't resize it every step. it only shrinks once everything fits in a quarter of it
+fn fit_size<'a>(size: u32, columns: impl Iterator<Item = &'a UVec2>) -> u32 {
+    let extent = columns.map(|chunk| chunk.max_element() + 1).max().unwrap_or(1);
+    let mut size = size;
+    while size < extent { size *= 2; }
+    while size > START_CHUNKS && extent * 4 <= size { size /= 2; }
+    size
+}
+
+// a fresh grid of the new size with the given columns carried over on every level, whatever else
+// the old grid had goes with it
+fn resize_grid(grid: &mut WorldGrid, size: u32, carried: impl Iterator<Item = UVec2>) {
+    let mut resized = new_grid(size);
+    for chunk in carried {
+        for cell in chunk_cells(chunk) {
+            for z in 0..LEVELS {
+                let cell = cell.extend(z);
+                if let Some(nav) = grid.nav(cell) { resized.set_nav(cell, nav); }
+            }
+        }
+    }
+    resized.build();
+    *grid = resized;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_doubles_to_reach_a_column() {
+        assert_eq!(fit_size(4, [UVec2::new(4, 0)].iter()), 8);
+        assert_eq!(fit_size(4, [UVec2::new(1, 20)].iter()), 32);
+    }
+
+    #[test]
+    fn grid_keeps_its_size_near_the_edge() {
+        assert_eq!(fit_size(8, [UVec2::new(3, 3)].iter()), 8);
+        assert_eq!(fit_size(8, [UVec2::new(7, 0)].iter()), 8);
+    }
+
+    #[test]
+    fn grid_shrinks_once_a_quarter_is_enough() {
+        assert_eq!(fit_size(32, [UVec2::new(1, 1)].iter()), START_CHUNKS);
+        assert_eq!(fit_size(32, [UVec2::new(7, 2)].iter()), 16);
+        assert_eq!(fit_size(32, [UVec2::new(8, 2)].iter()), 32);
+        assert_eq!(fit_size(32, std::iter::empty()), START_CHUNKS);
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use bevy_northstar::prelude::AgentPos;
+
+use crate::{item::ItemRegistry, job::{stop_agent, CurrentJob, JobKind}, player::Stockpile};
+
+// 1.0 is fully fed / rested, 0.0 is starving / exhausted
+#[derive(Debug, Component)]
+pub struct Needs {
+    pub hunger: f32,
+    pub rest: f32
+}
+
+impl Needs {
+    pub fn full() -> Needs {
+        Needs { hunger: 1.0, rest: 1.0 }
+    }
+
+    // multiplier applied to dig and build speed
+    pub fn productivity(&self) -> f32 {
+        need_factor(self.hunger) * need_factor(self.rest)
+    }
+}
+
+fn need_factor(value: f32) -> f32 {
+    if value <= 0.0 { 0.3 } else if value < NEED_LOW { 0.6 } else { 1.0 }
+}
+
+pub struct WorkerNeeds;
+
+impl Plugin for WorkerNeeds {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (decay_needs, preempt_for_needs, satisfy_needs).chain());
+    }
+}
+
+const HUNGER_SECS: f32 = 240.0; // time for a fed worker to starve
+const REST_SECS: f32 = 360.0; // time for a rested worker to be exhausted
+const NEED_LOW: f32 = 0.3; // below this the worker drops its work to take care of itself
+
+fn decay_needs(time: Res<Time>, mut workers: Query<(&mut Needs, Option<&CurrentJob>)>) {
+    for (mut needs, job) in &mut workers {
+        let kind = job.map(|j| j.kind);
+        if kind != Some(JobKind::Eat) { needs.hunger = (needs.hunger - time.delta_secs() / HUNGER_SECS).max(0.0); }
+        if kind != Some(JobKind::Sleep) { needs.rest = (needs.rest - time.delta_secs() / REST_SECS).max(0.0); }
+    }
+}
+
+// hunger goes first, a worker only eats if there is food in the stockpile
+fn preempt_for_needs(
+    mut commands: Commands,
+    items: Res<ItemRegistry>,
+    mut stockpile: ResMut<Stockpile>,
+    workers: Query<(Entity, &AgentPos, &Needs, Option<&CurrentJob>)>,
+) {
+    let ration = items.by_key("ration");
+
+    for (entity, agent_pos, needs, job) in &workers {
+        if job.is_some_and(|j| j.kind.is_need()) { continue; }
+
+        // without rations in the item list there is nothing to eat
+        let kind = if needs.hunger < NEED_LOW && let Some(ration) = ration && stockpile.items.count(ration) > 0 {
+            stockpile.items.take_all(&[(ration, 1)]);
+            JobKind::Eat
+        } else if needs.rest < NEED_LOW {
+            JobKind::Sleep
+        } else {
+            continue;
+        };
+
+        // the order the worker had goes back on the board
+        stop_agent(&mut commands, entity);
+        commands.entity(entity).insert(CurrentJob::in_place(kind, agent_pos.0));
+    }
+}
+
+fn satisfy_needs(mut commands: Commands, time: Res<Time>, mut workers: Query<(Entity, &mut Needs, &mut CurrentJob)>) {
+    for (entity, mut needs, mut job) in &mut workers {
+        if !job.kind.is_need() { continue; }
+
+        job.progress += time.delta_secs();
+        let done = (job.progress / job.kind.work()).min(1.0);
+
+        match job.kind {
+            JobKind::Eat => { if done >= 1.0 { needs.hunger = 1.0; } },
+            JobKind::Sleep => { needs.rest = needs.rest.max(done); },
+            _ => {}
+        }
+
+        if done >= 1.0 { commands.entity(entity).remove::<CurrentJob>(); }
+    }
+}
@@ -0,0 +1,30 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{belt::Belt, light::LightSource, power::PowerNode, stairs::Stairs, support::Support, terrain::Paving, water::WaterMap};
+
+// everything that can take up a cell, only one of them fits on it. every placement and the X tool
+// ask here first. machines, pumps and lamps are counted through their power node
+#[derive(SystemParam)]
+pub struct Occupancy<'w, 's> {
+    water: Res<'w, WaterMap>,
+    belts: Query<'w, 's, &'static Belt>,
+    nodes: Query<'w, 's, &'static PowerNode>,
+    lights: Query<'w, 's, &'static LightSource>,
+    supports: Query<'w, 's, &'static Support>,
+    pavings: Query<'w, 's, &'static Paving>,
+    stairs: Query<'w, 's, &'static Stairs>,
+}
+
+impl Occupancy<'_, '_> {
+    // planned supports and paving count too, and so does deep water. a built road or floor is just
+    // ground, things go on top of it
+    pub fn is_taken(&self, cell: UVec3) -> bool {
+        self.water.is_flooded(cell)
+            || self.belts.iter().any(|b| b.cell == cell)
+            || self.nodes.iter().any(|n| n.cell == cell)
+            || self.lights.iter().any(|l| l.cell == cell)
+            || self.supports.iter().any(|s| s.cell == cell)
+            || self.pavings.iter().any(|p| p.cell == cell && !p.built)
+            || self.stairs.iter().any(|s| s.cell == cell)
+    }
+}
@@ -1,8 +1,45 @@
 use bevy::{platform::collections::HashSet, prelude::*};
 
+use crate::{item::{ItemCounts, ItemId, ItemRegistry}, world::{Level, TILESIZE}};
+
 #[derive(Resource)]
 pub struct PlayerInventory { pub stacks: Vec<ObjectStack> }
 
+impl PlayerInventory {
+    // tops up the existing stacks first, then opens new ones capped at the item stack size
+    pub fn add(&mut self, item: ItemId, mut amount: u32, registry: &ItemRegistry) {
+        let stack_size = registry.get(item).stack_size.max(1);
+
+        for stack in self.stacks.iter_mut().filter(|s| s.item == item) {
+            let room = stack_size.saturating_sub(stack.total).min(amount);
+            stack.total += room;
+            amount -= room;
+        }
+
+        while amount > 0 {
+            let total = amount.min(stack_size);
+            self.stacks.push(ObjectStack { item, total, assigned:false, related_entity:Entity::from_raw(0) });
+            amount -= total;
+        }
+    }
+
+    pub fn take_one(&mut self, item: ItemId) -> bool {
+        let Some(index) = self.stacks.iter().rposition(|s| s.item == item) else { return false; };
+
+        self.stacks[index].total -= 1;
+        if self.stacks[index].total == 0 { self.stacks.remove(index); }
+        true
+    }
+
+    pub fn count(&self, item: ItemId) -> u32 {
+        self.stacks.iter().filter(|s| s.item == item).map(|s| s.total).sum()
+    }
+}
+
+// colony wide storage of raw materials and crafted parts, haulers drop off at its cell
+#[derive(Resource)]
+pub struct Stockpile { pub items: ItemCounts, pub cell: UVec3 }
+
 #[derive(Resource)]
 pub struct SelectedEntities { pub entities: HashSet<Entity> }
 
@@ -12,23 +49,43 @@ pub struct CanPlayerBuild { pub enabled: bool  } // player pointing at ui entity
 // playerinventory / uiinventoryslot
 #[derive(Debug, Component)]
 pub struct ObjectStack {
-    pub item: Object,
-    pub total: i32,
+    pub item: ItemId,
+    pub total: u32,
     pub assigned: bool, // ui uses this to bind it to the ui
     pub related_entity: Entity
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    None,
+    Action
+}
+
+// whatever the player is holding from the action bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Held {
+    Tool(Tool),
+    Item(ItemId)
+}
+
 #[derive(Debug, Resource)]
 pub struct ObjectSelected {
-    pub object: Object,
+    pub held: Held,
     pub ui_entity: Entity
 }
 
-#[derive(Debug, Component, PartialEq, Eq, Clone, Copy)]
-pub enum Object {
-    None,
-    Action,
-    Worker
+impl ObjectSelected {
+    // selecting and commanding only happens with a tool in hand
+    pub fn is_tool(&self) -> bool {
+        matches!(self.held, Held::Tool(_))
+    }
+
+    pub fn item(&self) -> Option<ItemId> {
+        match self.held {
+            Held::Item(item) => Some(item),
+            Held::Tool(_) => None
+        }
+    }
 }
 
 pub struct Player;
@@ -37,13 +94,62 @@ impl Plugin for Player {
     fn build(&self, app: &mut App) {
         app.insert_resource(CanPlayerBuild { enabled: false });
         app.insert_resource(PlayerInventory { stacks: Vec::new() });
+        app.insert_resource(Stockpile { items: ItemCounts::default(), cell: UVec3::new(6, 2, 0) });
         app.insert_resource(SelectedEntities { entities: HashSet::new() });
-        app.insert_resource(ObjectSelected { object: Object::Action, ui_entity: Entity::from_raw(0)});
+        app.insert_resource(ObjectSelected { held: Held::Tool(Tool::Action), ui_entity: Entity::from_raw(0)});
 
         app.add_systems(Startup, setup);
     }
 }
 
-fn setup(mut inv: ResMut<PlayerInventory>) {
-    inv.stacks.push(ObjectStack { item: Object::Worker, total:9, assigned:false, related_entity:Entity::from_raw(0) });
-}
\ No newline at end of file
+fn setup(mut commands: Commands, mut inv: ResMut<PlayerInventory>, mut stockpile: ResMut<Stockpile>, registry: Res<ItemRegistry>) {
+    let cell = stockpile.cell;
+    commands.spawn((
+        Name::new("Stockpile"),
+        Level(cell.z),
+        Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, 2.0),
+        Sprite { color: Color::srgba(0.9, 0.8, 0.3, 0.5), custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)), ..default() }
+    ));
+
+    for (key, total) in [("worker", 9), ("smelter", 2), ("workbench", 2), ("belt", 40), ("splitter", 4), ("merger", 4), ("coal_burner", 2), ("water_wheel", 2), ("power_pole", 20), ("torch", 10), ("lamp", 4), ("pillar", 8), ("beam", 12), ("pump", 2), ("road", 40), ("floor", 20), ("stairs", 4)] {
+        if let Some(item) = registry.by_key(key) { inv.add(item, total, &registry); }
+    }
+
+    for (key, total) in [("copper_ore", 20), ("coal", 10), ("ration", 30)] {
+        if let Some(item) = registry.by_key(key) { stockpile.items.add(item, total); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::test_registry;
+
+    #[test]
+    fn add_tops_up_before_opening_stacks() {
+        let registry = test_registry(&["belt"], 10);
+        let belt = ItemId(0);
+        let mut inventory = PlayerInventory { stacks: Vec::new() };
+
+        inventory.add(belt, 4, &registry);
+        inventory.add(belt, 13, &registry);
+
+        let totals: Vec<u32> = inventory.stacks.iter().map(|s| s.total).collect();
+        assert_eq!(totals, vec![10, 7]);
+        assert_eq!(inventory.count(belt), 17);
+    }
+
+    #[test]
+    fn take_one_empties_the_last_stack_first() {
+        let registry = test_registry(&["belt"], 2);
+        let belt = ItemId(0);
+        let mut inventory = PlayerInventory { stacks: Vec::new() };
+        inventory.add(belt, 3, &registry);
+
+        assert!(inventory.take_one(belt));
+        assert_eq!(inventory.stacks.len(), 1);
+        assert!(inventory.take_one(belt));
+        assert!(inventory.take_one(belt));
+        assert!(!inventory.take_one(belt));
+    }
+}
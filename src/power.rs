@@ -0,0 +1,262 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_northstar::nav::Nav;
+
+use crate::{fog::FogOfWar, item::{ItemRegistry, Placeable}, machine::Machine, mouse::GridClicked, nav_edit::NavEdits, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory, Stockpile}, water::WaterMap, world::{Level, ViewLevel, TILESIZE, WorldGrid}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerKind {
+    CoalBurner,
+    WaterWheel,
+    Pole
+}
+
+// anything that takes part in a power network. nodes connect when they are within reach of each other
+#[derive(Debug, Component)]
+pub struct PowerNode {
+    pub cell: UVec3,
+    pub reach: u32,
+    pub network: Option<usize>
+}
+
+#[derive(Debug, Component)]
+pub struct Generator {
+    pub kind: PowerKind,
+    pub output: f32,
+    fuel_left: f32 // seconds of burn time left from the last piece of fuel
+}
+
+#[derive(Debug, Component)]
+pub struct PowerConsumer {
+    pub draw: f32,
+    pub satisfaction: f32 // 0..1, scales how fast the consumer works
+}
+
+impl PowerConsumer {
+    pub fn new(draw: f32) -> PowerConsumer {
+        PowerConsumer { draw, satisfaction: 0.0 }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NetworkStats { pub supply: f32, pub demand: f32 }
+
+impl NetworkStats {
+    pub fn satisfaction(&self) -> f32 {
+        if self.demand <= 0.0 { 1.0 } else { (self.supply / self.demand).min(1.0) }
+    }
+}
+
+#[derive(Resource)]
+pub struct PowerNetworks {
+    pub networks: Vec<NetworkStats>,
+    edges: Vec<(UVec3, UVec3, usize)> // wires, kept for the overlay
+}
+
+#[derive(Resource)]
+struct PowerOverlay { enabled: bool }
+
+pub struct Power;
+
+impl Plugin for Power {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PowerNetworks { networks: Vec::new(), edges: Vec::new() });
+        app.insert_resource(PowerOverlay { enabled: false });
+
+        app.add_systems(Update, (place_power_building, toggle_power_overlay));
+        app.add_systems(Update, (rebuild_networks, balance_networks, draw_power_overlay).chain());
+    }
+}
+
+const POWER_Z_AXIS: f32 = 3.0;
+const POLE_REACH: u32 = 5;
+const BUILDING_REACH: u32 = 1;
+const COAL_BURN_SECS: f32 = 10.0;
+const WHEEL_FULL_FLOW: f32 = 0.1; // water running past a wheel per update to turn it at full speed
+
+impl PowerKind {
+    fn output(&self) -> f32 {
+        match self {
+            PowerKind::CoalBurner => 60.0,
+            PowerKind::WaterWheel => 20.0,
+            PowerKind::Pole => 0.0
+        }
+    }
+}
+
+fn place_power_building(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    mut edits: ResMut<NavEdits>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    items: Res<ItemRegistry>,
+    mut inventory: ResMut<PlayerInventory>,
+    fog: Res<FogOfWar>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    let Some(Placeable::Power(kind)) = items.get(item).placeable else { return; };
+
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+
+    if !matches!(grid.nav(cell), Some(Nav::Passable(_))) { return; }
+    if occupancy.is_taken(cell) { return; }
+    if !inventory.take_one(item) { return; }
+
+    let transform = Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, POWER_Z_AXIS);
+    let color = items.get(item).icon;
+
+    if kind == PowerKind::Pole {
+        // poles stay walkable
+        commands.spawn((
+            Name::new("Power pole"),
+            PowerNode { cell, reach: POLE_REACH, network: None },
+            Level(cell.z),
+            transform,
+            Sprite { color, custom_size: Some(Vec2::new(4.0, 4.0)), ..default() }
+        ));
+        return;
+    }
+
+    edits.set(cell, Nav::Impassable);
+
+    commands.spawn((
+        Name::new(format!("{:?}", kind)),
+        PowerNode { cell, reach: BUILDING_REACH, network: None },
+        Generator { kind, output: kind.output(), fuel_left: 0.0 },
+        Level(cell.z),
+        transform,
+        Sprite { color, custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)), ..default() }
+    ));
+}
+
+fn toggle_power_overlay(input: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<PowerOverlay>) {
+    if input.just_pressed(KeyCode::KeyV) { overlay.enabled = !overlay.enabled; }
+}
+
+fn in_reach(a: &PowerNode, b: &PowerNode) -> bool {
+    let distance = (a.cell.x.abs_diff(b.cell.x)).max(a.cell.y.abs_diff(b.cell.y));
+    a.cell.z == b.cell.z && distance <= a.reach.max(b.reach)
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+// networks are the connected components of the node graph, only recomputed when nodes come or go
+fn rebuild_networks(
+    mut networks: ResMut<PowerNetworks>,
+    added: Query<(), Added<PowerNode>>,
+    mut removed: RemovedComponents<PowerNode>,
+    mut nodes: Query<&mut PowerNode>,
+) {
+    if added.is_empty() && removed.read().count() == 0 { return; }
+
+    let cells: Vec<UVec3> = nodes.iter().map(|n| n.cell).collect();
+    let mut parents: Vec<usize> = (0..cells.len()).collect();
+    let mut edges = Vec::new();
+
+    {
+        let all: Vec<&PowerNode> = nodes.iter().collect();
+        for a in 0..all.len() {
+            for b in (a + 1)..all.len() {
+                if !in_reach(all[a], all[b]) { continue; }
+                edges.push((a, b));
+                let (root_a, root_b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+                parents[root_a] = root_b;
+            }
+        }
+    }
+
+    let mut network_ids: HashMap<usize, usize> = HashMap::new();
+    for (i, mut node) in nodes.iter_mut().enumerate() {
+        let root = find_root(&mut parents, i);
+        let next_id = network_ids.len();
+        node.network = Some(*network_ids.entry(root).or_insert(next_id));
+    }
+
+    networks.networks = vec![NetworkStats::default(); network_ids.len()];
+    networks.edges = edges.into_iter()
+        .map(|(a, b)| (cells[a], cells[b], network_ids[&find_root(&mut parents, a)]))
+        .collect();
+}
+
+// demand comes from busy consumers, supply from generators that have fuel or water running past
+// them. when demand is higher than supply every consumer on the network slows down by the same ratio
+fn balance_networks(
+    time: Res<Time>,
+    mut networks: ResMut<PowerNetworks>,
+    mut stockpile: ResMut<Stockpile>,
+    items: Res<ItemRegistry>,
+    water: Res<WaterMap>,
+    mut generators: Query<(&PowerNode, &mut Generator)>,
+    mut consumers: Query<(&PowerNode, &mut PowerConsumer, Option<&Machine>)>,
+) {
+    for stats in networks.networks.iter_mut() { *stats = NetworkStats::default(); }
+
+    for (node, consumer, machine) in &consumers {
+        let busy = machine.is_none_or(|m| m.progress.is_some());
+        if let Some(stats) = node.network.and_then(|n| networks.networks.get_mut(n)) && busy {
+            stats.demand += consumer.draw;
+        }
+    }
+
+    let coal = items.by_key("coal");
+    for (node, mut generator) in &mut generators {
+        let Some(stats) = node.network.and_then(|n| networks.networks.get_mut(n)) else { continue; };
+
+        if generator.kind == PowerKind::CoalBurner {
+            // only burns fuel while something on the network wants power
+            if stats.demand <= 0.0 { continue; }
+            if generator.fuel_left <= 0.0 {
+                let Some(coal) = coal else { continue; };
+                if stockpile.items.count(coal) == 0 { continue; }
+                stockpile.items.take_all(&[(coal, 1)]);
+                generator.fuel_left = COAL_BURN_SECS;
+            }
+            generator.fuel_left -= time.delta_secs();
+        }
+
+        // standing in a still lake, or next to nothing at all, the wheel doesn't turn
+        if generator.kind == PowerKind::WaterWheel {
+            let output = generator.kind.output() * (water.flow_around(node.cell) / WHEEL_FULL_FLOW).min(1.0);
+            if generator.output != output { generator.output = output; }
+        }
+
+        stats.supply += generator.output;
+    }
+
+    for (node, mut consumer, _) in &mut consumers {
+        let satisfaction = node.network.and_then(|n| networks.networks.get(n)).map_or(0.0, |s| s.satisfaction());
+        if consumer.satisfaction != satisfaction { consumer.satisfaction = satisfaction; }
+    }
+}
+
+fn draw_power_overlay(mut gizmos: Gizmos, overlay: Res<PowerOverlay>, view: Res<ViewLevel>, networks: Res<PowerNetworks>, nodes: Query<&PowerNode>) {
+    if !overlay.enabled { return; }
+
+    let color = |network: Option<usize>| {
+        match network.and_then(|n| networks.networks.get(n)).map(|s| s.satisfaction()) {
+            Some(s) if s >= 1.0 => Color::srgb(0.3, 0.9, 0.3),
+            Some(s) if s > 0.0 => Color::srgb(0.95, 0.8, 0.2),
+            _ => Color::srgb(0.9, 0.25, 0.2)
+        }
+    };
+    let to_world = |cell: UVec3| Vec2::new(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32);
+
+    for (a, b, network) in networks.edges.iter().filter(|(a, _, _)| a.z == view.0) {
+        gizmos.line_2d(to_world(*a), to_world(*b), color(Some(*network)));
+    }
+
+    for node in nodes.iter().filter(|n| n.cell.z == view.0) {
+        gizmos.circle_2d(to_world(node.cell), TILESIZE as f32 / 2.0, color(node.network));
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use bevy::prelude::*;
+
+use crate::{item::{ItemId, ItemRegistry}, machine::MachineKind};
+
+const RECIPES_PATH: &str = "assets/data/recipes.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecipeId(pub usize);
+
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    pub name: String,
+    pub machine: MachineKind,
+    pub duration: f32, // seconds
+    pub inputs: Vec<(ItemId, u32)>,
+    pub outputs: Vec<(ItemId, u32)>
+}
+
+#[derive(Debug, Resource)]
+pub struct RecipeRegistry { pub recipes: Vec<Recipe> }
+
+impl RecipeRegistry {
+    pub fn get(&self, id: RecipeId) -> Option<&Recipe> {
+        self.recipes.get(id.0)
+    }
+
+    // every recipe a given machine type is able to run
+    pub fn for_machine(&self, kind: MachineKind) -> impl Iterator<Item = (RecipeId, &Recipe)> {
+        self.recipes.iter().enumerate()
+            .filter(move |(_, r)| r.machine == kind)
+            .map(|(i, r)| (RecipeId(i), r))
+    }
+}
+
+pub struct Recipes;
+
+impl Plugin for Recipes {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RecipeRegistry { recipes: Vec::new() });
+        app.add_systems(Startup, load_recipes);
+    }
+}
+
+fn load_recipes(mut registry: ResMut<RecipeRegistry>, items: Res<ItemRegistry>) {
+    let Ok(file) = fs::read_to_string(RECIPES_PATH) else {
+        warn!("could not read {}", RECIPES_PATH);
+        return;
+    };
+
+    for (line_number, line) in file.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+
+        match parse_recipe(line, &items) {
+            Some(recipe) => registry.recipes.push(recipe),
+            None => warn!("{}:{} invalid recipe: {}", RECIPES_PATH, line_number + 1, line)
+        }
+    }
+}
+
+// name | machine | duration | inputs | outputs
+fn parse_recipe(line: &str, items: &ItemRegistry) -> Option<Recipe> {
+    let fields: Vec<&str> = line.split('|').map(|f| f.trim()).collect();
+    if fields.len() != 5 { return None; }
+
+    Some(Recipe {
+        name: fields[0].to_string(),
+        machine: MachineKind::from_name(fields[1])?,
+        duration: fields[2].parse().ok()?,
+        inputs: parse_item_list(fields[3], items)?,
+        outputs: parse_item_list(fields[4], items)?
+    })
+}
+
+// copper_ore:2, coal:1 - every item has to exist in the item registry
+fn parse_item_list(field: &str, items: &ItemRegistry) -> Option<Vec<(ItemId, u32)>> {
+    field.split(',')
+        .map(|entry| {
+            let (item, amount) = entry.trim().split_once(':')?;
+            Some((items.by_key(item.trim())?, amount.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::test_registry;
+
+    #[test]
+    fn parses_a_full_line() {
+        let items = test_registry(&["copper_ore", "coal", "copper_ingot"], 50);
+        let recipe = parse_recipe("copper_ingot | smelter | 4.0 | copper_ore:2, coal:1 | copper_ingot:1", &items).unwrap();
+
+        assert_eq!(recipe.name, "copper_ingot");
+        assert_eq!(recipe.machine, MachineKind::Smelter);
+        assert_eq!(recipe.duration, 4.0);
+        assert_eq!(recipe.inputs, vec![(ItemId(0), 2), (ItemId(1), 1)]);
+        assert_eq!(recipe.outputs, vec![(ItemId(2), 1)]);
+    }
+
+    #[test]
+    fn rejects_unknown_items_and_machines() {
+        let items = test_registry(&["copper_ore", "copper_ingot"], 50);
+        assert!(parse_recipe("copper_ingot | smelter | 4.0 | tin_ore:2 | copper_ingot:1", &items).is_none());
+        assert!(parse_recipe("copper_ingot | forge | 4.0 | copper_ore:2 | copper_ingot:1", &items).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_fields() {
+        let items = test_registry(&["copper_ore", "copper_ingot"], 50);
+        assert!(parse_recipe("copper_ingot | smelter | 4.0 | copper_ore:2", &items).is_none());
+        assert!(parse_recipe("copper_ingot | smelter | fast | copper_ore:2 | copper_ingot:1", &items).is_none());
+        assert!(parse_recipe("copper_ingot | smelter | 4.0 | copper_ore | copper_ingot:1", &items).is_none());
+        assert!(parse_recipe("copper_ingot | smelter | 4.0 | copper_ore:two | copper_ingot:1", &items).is_none());
+    }
+}
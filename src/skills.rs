@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Skill {
+    Mining,
+    Construction,
+    Hauling,
+    Operating
+}
+
+impl Skill {
+    pub const ALL: [Skill; 4] = [Skill::Mining, Skill::Construction, Skill::Hauling, Skill::Operating];
+
+    fn index(&self) -> usize {
+        match self {
+            Skill::Mining => 0,
+            Skill::Construction => 1,
+            Skill::Hauling => 2,
+            Skill::Operating => 3
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Skills {
+    levels: [u32; 4],
+    experience: [f32; 4]
+}
+
+impl Skills {
+    // starting levels are spread out from the seed so no two workers begin the same
+    pub fn seeded(seed: u32) -> Skills {
+        let mut state = seed.wrapping_mul(2654435761).wrapping_add(12345);
+        let mut levels = [0; 4];
+        for level in levels.iter_mut() {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            *level = (state >> 16) % (STARTING_LEVEL_MAX + 1);
+        }
+        Skills { levels, experience: [0.0; 4] }
+    }
+
+    pub fn level(&self, skill: Skill) -> u32 {
+        self.levels[skill.index()]
+    }
+
+    // fraction of the way to the next level
+    pub fn progress(&self, skill: Skill) -> f32 {
+        let level = self.level(skill);
+        if level >= MAX_LEVEL { return 1.0; }
+        self.experience[skill.index()] / experience_for(level + 1)
+    }
+
+    // work speed multiplier, untrained workers still get the job done
+    pub fn speed(&self, skill: Skill) -> f32 {
+        1.0 + self.level(skill) as f32 * SPEED_PER_LEVEL
+    }
+
+    pub fn gain(&mut self, skill: Skill, experience: f32) {
+        let i = skill.index();
+        if self.levels[i] >= MAX_LEVEL { return; }
+
+        self.experience[i] += experience;
+        while self.levels[i] < MAX_LEVEL && self.experience[i] >= experience_for(self.levels[i] + 1) {
+            self.experience[i] -= experience_for(self.levels[i] + 1);
+            self.levels[i] += 1;
+        }
+    }
+}
+
+const MAX_LEVEL: u32 = 10;
+const STARTING_LEVEL_MAX: u32 = 3;
+const SPEED_PER_LEVEL: f32 = 0.1;
+
+// seconds of work needed to reach a level
+fn experience_for(level: u32) -> f32 {
+    20.0 * level as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn untrained() -> Skills {
+        Skills { levels: [0; 4], experience: [0.0; 4] }
+    }
+
+    #[test]
+    fn gain_levels_up_and_keeps_the_rest() {
+        let mut skills = untrained();
+        skills.gain(Skill::Mining, experience_for(1) + 5.0);
+
+        assert_eq!(skills.level(Skill::Mining), 1);
+        assert_eq!(skills.progress(Skill::Mining), 5.0 / experience_for(2));
+        assert_eq!(skills.level(Skill::Hauling), 0);
+    }
+
+    #[test]
+    fn gain_can_skip_several_levels() {
+        let mut skills = untrained();
+        skills.gain(Skill::Operating, experience_for(1) + experience_for(2) + experience_for(3));
+
+        assert_eq!(skills.level(Skill::Operating), 3);
+        assert_eq!(skills.progress(Skill::Operating), 0.0);
+    }
+
+    #[test]
+    fn gain_stops_at_the_max_level() {
+        let mut skills = untrained();
+        skills.gain(Skill::Construction, 1_000_000.0);
+
+        assert_eq!(skills.level(Skill::Construction), MAX_LEVEL);
+        assert_eq!(skills.progress(Skill::Construction), 1.0);
+        assert_eq!(skills.speed(Skill::Construction), 1.0 + MAX_LEVEL as f32 * SPEED_PER_LEVEL);
+    }
+
+    #[test]
+    fn seeded_levels_stay_in_the_starting_range() {
+        for seed in 0..50 {
+            let skills = Skills::seeded(seed);
+            assert!(Skill::ALL.iter().all(|skill| skills.level(*skill) <= STARTING_LEVEL_MAX));
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use bevy_northstar::{nav::{Nav, Portal}};
+
+use crate::{fog::FogOfWar, item::{ItemRegistry, Placeable}, job::pick_tier, light::LightMap, mouse::GridClicked, nav_edit::NavEdits, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory, Stockpile}, terrain::TerrainMap, world::{Level, LEVELS, TILESIZE, WorldGrid}, worldgen::WorldGen};
+
+// one end of a staircase, the other end is straight above or below it.
+// stairs are the only way between levels
+#[derive(Debug, Component)]
+pub struct Stairs { pub cell: UVec3, pub other: UVec3 }
+
+pub struct Stairways;
+
+impl Plugin for Stairways {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, place_stairs);
+    }
+}
+
+const STAIRS_Z_AXIS: f32 = 2.5;
+const STAIRS_COST: u32 = 3;
+
+// goes down from the clicked cell, digging out the landing on the level below when it is still rock.
+// rock too hard for the colony's picks can't be dug through this way either
+fn place_stairs(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    mut edits: ResMut<NavEdits>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    items: Res<ItemRegistry>,
+    stockpile: Res<Stockpile>,
+    world_gen: Res<WorldGen>,
+    mut terrain: ResMut<TerrainMap>,
+    mut fog: ResMut<FogOfWar>,
+    mut light: ResMut<LightMap>,
+    mut inventory: ResMut<PlayerInventory>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    if items.get(item).placeable != Some(Placeable::Stairs) { return; }
+
+    let top = grid_position.position;
+    if !fog.is_explored(top) || top.z + 1 >= LEVELS { return; }
+    let bottom = top + UVec3::Z;
+
+    // both ends need to be free, the landing may be rock but not a building
+    if !matches!(grid.nav(top), Some(Nav::Passable(_))) { return; }
+    if !matches!(grid.nav(bottom), Some(Nav::Passable(_) | Nav::Impassable)) { return; }
+    if occupancy.is_taken(top) || occupancy.is_taken(bottom) { return; }
+    let rock = matches!(grid.nav(bottom), Some(Nav::Impassable)) && terrain.is_natural_rock(bottom);
+    if rock && world_gen.value.rock(bottom.as_ivec3()).tier() > pick_tier(&stockpile, &items) { return; }
+    if !inventory.take_one(item) { return; }
+
+    // the grid adds the way back up itself
+    edits.set(top, Nav::Portal(Portal::to(bottom, STAIRS_COST, false)));
+
+    light.roof(bottom);
+    terrain.dig(bottom);
+    fog.explored.insert(bottom.as_ivec3());
+
+    let color = items.get(item).icon;
+    for (cell, other) in [(top, bottom), (bottom, top)] {
+        commands.spawn((
+            Name::new("Stairs"),
+            Stairs { cell, other },
+            Level(cell.z),
+            Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, STAIRS_Z_AXIS),
+            Sprite { color, custom_size: Some(Vec2::new(TILESIZE as f32 * 0.8, TILESIZE as f32 * 0.8)), ..default() }
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_northstar::{nav::Nav, prelude::AgentPos};
+
+use crate::{fog::FogOfWar, item::{ItemRegistry, Placeable}, light::LightMap, mouse::GridClicked, nav_edit::NavEdits, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory}, terrain::{TerrainKind, TerrainMap}, world::{is_open, Level, TILESIZE, WorldGrid}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupportKind {
+    Pillar, // blocks the cell like rock
+    Beam // holds the ceiling up and stays walkable
+}
+
+// placed as a plan, a worker has to build it before it holds anything up
+#[derive(Debug, Component)]
+pub struct Support {
+    pub kind: SupportKind,
+    pub cell: UVec3,
+    pub built: bool
+}
+
+// seconds every unsupported ceiling cell has been under strain
+#[derive(Resource)]
+pub struct Stability {
+    pub strain: HashMap<IVec3, f32>
+}
+
+#[derive(Debug, Component)]
+struct CaveInWarning { cell: IVec3 }
+
+#[derive(Resource)]
+struct StabilityClock(Timer);
+
+pub struct Supports;
+
+impl Plugin for Supports {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Stability { strain: HashMap::new() });
+        app.insert_resource(StabilityClock(Timer::from_seconds(STABILITY_UPDATE_SECS, TimerMode::Repeating)));
+
+        app.add_systems(Update, (place_support, show_built_supports));
+        app.add_systems(Update, (check_stability, collapse_ceilings, update_warnings).chain());
+    }
+}
+
+const SUPPORT_Z_AXIS: f32 = 3.0;
+const WARNING_Z_AXIS: f32 = 3.7;
+const STABILITY_UPDATE_SECS: f32 = 1.0;
+const MAX_UNSUPPORTED_SPAN: u32 = 3; // cells of open ceiling away from the nearest support
+const COLLAPSE_SECS: f32 = 20.0;
+
+fn place_support(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    items: Res<ItemRegistry>,
+    fog: Res<FogOfWar>,
+    mut inventory: ResMut<PlayerInventory>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    let Some(Placeable::Support(kind)) = items.get(item).placeable else { return; };
+
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+
+    if !matches!(grid.nav(cell), Some(Nav::Passable(_))) { return; }
+    if occupancy.is_taken(cell) { return; }
+    if !inventory.take_one(item) { return; }
+
+    let size = if kind == SupportKind::Pillar { TILESIZE as f32 * 0.75 } else { TILESIZE as f32 };
+    commands.spawn((
+        Name::new(format!("{:?}", kind)),
+        Support { kind, cell, built: false },
+        Level(cell.z),
+        Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, SUPPORT_Z_AXIS),
+        Sprite { color: items.get(item).icon.with_alpha(0.35), custom_size: Some(Vec2::new(size, size / 3.0)), ..default() }
+    ));
+}
+
+fn show_built_supports(mut supports: Query<(&Support, &mut Sprite), Changed<Support>>) {
+    for (support, mut sprite) in &mut supports {
+        if support.built { sprite.color = sprite.color.with_alpha(1.0); }
+    }
+}
+
+// how far every open ceiling cell is from the nearest thing holding it up. rock, rubble, walls and
+// pillars hold up the cells around them, a beam holds up its own cell
+fn ceiling_spans(grid: &WorldGrid, light: &LightMap, beams: &[IVec3]) -> HashMap<IVec3, u32> {
+    let mut spans: HashMap<IVec3, u32> = HashMap::new();
+    let mut queue: VecDeque<IVec3> = VecDeque::new();
+
+    for cell in light.roofed.iter().copied() {
+        if !is_open(grid, cell) { continue; }
+
+        let propped = beams.contains(&cell);
+        let next_to_rock = (-1..=1).flat_map(|x| (-1..=1).map(move |y| IVec3::new(x, y, 0)))
+            .any(|offset| offset != IVec3::ZERO && !is_open(grid, cell + offset));
+
+        if propped || next_to_rock {
+            spans.insert(cell, if propped { 0 } else { 1 });
+            queue.push_back(cell);
+        }
+    }
+
+    while let Some(cell) = queue.pop_front() {
+        let span = spans[&cell];
+        for offset in [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y] {
+            let next = cell + offset;
+            if !light.roofed.contains(&next) || !is_open(grid, next) { continue; }
+            if spans.get(&next).is_some_and(|s| *s <= span + 1) { continue; }
+            spans.insert(next, span + 1);
+            queue.push_back(next);
+        }
+    }
+
+    // open ceiling with nothing anywhere under it, only happens in huge caverns
+    for cell in light.roofed.iter().copied() {
+        if is_open(grid, cell) { spans.entry(cell).or_insert(u32::MAX); }
+    }
+    spans
+}
+
+fn check_stability(
+    time: Res<Time>,
+    mut clock: ResMut<StabilityClock>,
+    mut stability: ResMut<Stability>,
+    grid: Single<&WorldGrid>,
+    light: Res<LightMap>,
+    supports: Query<&Support>,
+) {
+    if !clock.0.tick(time.delta()).just_finished() { return; }
+
+    let beams: Vec<IVec3> = supports.iter()
+        .filter(|s| s.built && s.kind == SupportKind::Beam)
+        .map(|s| s.cell.as_ivec3())
+        .collect();
+    let spans = ceiling_spans(grid.into_inner(), &light, &beams);
+
+    let mut strain = HashMap::new();
+    for (cell, span) in spans {
+        if span <= MAX_UNSUPPORTED_SPAN { continue; }
+        let before = stability.strain.get(&cell).copied().unwrap_or(0.0);
+        strain.insert(cell, before + STABILITY_UPDATE_SECS);
+    }
+    stability.strain = strain;
+}
+
+// strained ceiling comes down as rubble, which is rock again and props up what is left around it.
+// digging it out leaves rough floor behind. waits until the cell is clear so nobody gets buried
+fn collapse_ceilings(
+    mut edits: ResMut<NavEdits>,
+    mut stability: ResMut<Stability>,
+    mut terrain: ResMut<TerrainMap>,
+    agents: Query<&AgentPos>,
+) {
+    if !stability.is_changed() { return; }
+
+    let collapsing: Vec<IVec3> = stability.strain.iter()
+        .filter(|(cell, strain)| **strain >= COLLAPSE_SECS && !agents.iter().any(|a| a.0.as_ivec3() == **cell))
+        .map(|(cell, _)| *cell)
+        .collect();
+    if collapsing.is_empty() { return; }
+
+    for cell in collapsing {
+        edits.set(cell.as_uvec3(), Nav::Impassable);
+        terrain.set(cell.as_uvec3(), TerrainKind::Rubble);
+        stability.strain.remove(&cell);
+        warn!("cave-in at {} {} on level {}", cell.x, cell.y, cell.z);
+    }
+}
+
+// blinking markers over every cell that is going to come down, faster and redder the closer it is
+fn update_warnings(
+    mut commands: Commands,
+    time: Res<Time>,
+    stability: Res<Stability>,
+    mut warnings: Query<(Entity, &CaveInWarning, &mut Sprite)>,
+) {
+    for (entity, warning, mut sprite) in &mut warnings {
+        let Some(strain) = stability.strain.get(&warning.cell) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let danger = (strain / COLLAPSE_SECS).min(1.0);
+        let blink = (time.elapsed_secs() * (2.0 + 8.0 * danger)).sin() * 0.5 + 0.5;
+        sprite.color = Color::srgba(1.0, 0.7 * (1.0 - danger), 0.1, 0.2 + 0.4 * blink);
+    }
+
+    if !stability.is_changed() { return; }
+    for cell in stability.strain.keys() {
+        if warnings.iter().any(|(_, w, _)| w.cell == *cell) { continue; }
+        commands.spawn((
+            CaveInWarning { cell: *cell },
+            Level(cell.z as u32),
+            Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, WARNING_Z_AXIS),
+            Sprite { color: Color::srgba(1.0, 0.7, 0.1, 0.2), custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)), ..default() }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::test_grid;
+
+    // a 10 by 10 hall dug out on level 1, rock all around it
+    fn hall() -> (WorldGrid, LightMap) {
+        let rock: Vec<IVec3> = (0..12)
+            .flat_map(|x| (0..12).map(move |y| IVec3::new(x, y, 1)))
+            .filter(|cell| cell.x == 0 || cell.x == 11 || cell.y == 0 || cell.y == 11)
+            .collect();
+
+        let mut light = LightMap::default();
+        for x in 1..=10 {
+            for y in 1..=10 { light.roof(UVec3::new(x, y, 1)); }
+        }
+        (test_grid(&rock), light)
+    }
+
+    #[test]
+    fn spans_grow_away_from_the_walls() {
+        let (grid, light) = hall();
+        let spans = ceiling_spans(&grid, &light, &[]);
+
+        assert_eq!(spans[&IVec3::new(1, 1, 1)], 1);
+        assert_eq!(spans[&IVec3::new(2, 2, 1)], 2);
+        assert_eq!(spans[&IVec3::new(5, 5, 1)], 5);
+        assert!(!spans.contains_key(&IVec3::new(0, 5, 1)));
+    }
+
+    #[test]
+    fn beams_hold_up_their_own_cell() {
+        let (grid, light) = hall();
+        let spans = ceiling_spans(&grid, &light, &[IVec3::new(5, 5, 1)]);
+
+        assert_eq!(spans[&IVec3::new(5, 5, 1)], 0);
+        assert_eq!(spans[&IVec3::new(5, 6, 1)], 1);
+        assert_eq!(spans[&IVec3::new(6, 6, 1)], 2);
+    }
+
+    #[test]
+    fn open_ceiling_with_nothing_under_it_is_unsupported() {
+        let mut light = LightMap::default();
+        light.roof(UVec3::new(5, 5, 0));
+        let spans = ceiling_spans(&test_grid(&[]), &light, &[]);
+
+        assert_eq!(spans[&IVec3::new(5, 5, 0)], u32::MAX);
+    }
+}
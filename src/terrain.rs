@@ -0,0 +1,167 @@
+use bevy::{platform::collections::{HashMap, HashSet}, prelude::*};
+use bevy_northstar::nav::Nav;
+
+use crate::{fog::FogOfWar, item::{ItemRegistry, Placeable}, mouse::GridClicked, nav_edit::NavEdits, nav_stream::{chunk_cells, NavChunkLoaded, NavChunkUnloaded}, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory}, world::{Level, TILESIZE, WorldGrid}, worldgen::WorldGen};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainKind {
+    Ground,
+    Mud,
+    Rubble, // left behind when a cave-in is dug out again
+    Floor,
+    Road
+}
+
+impl TerrainKind {
+    // what a worker pays to walk over the cell, shallow water adds its own on top
+    pub fn cost(&self) -> u32 {
+        match self {
+            TerrainKind::Road => 1,
+            TerrainKind::Floor => 1,
+            TerrainKind::Ground => 2,
+            TerrainKind::Mud => 4,
+            TerrainKind::Rubble => 4
+        }
+    }
+}
+
+// ground under every cell, anything not in the map is plain ground. the cave levels start out as
+// rock, the cells dug out of it since are remembered
+#[derive(Resource, Default)]
+pub struct TerrainMap { kinds: HashMap<IVec3, TerrainKind>, dug: HashSet<IVec3> }
+
+impl TerrainMap {
+    pub fn kind(&self, cell: UVec3) -> TerrainKind {
+        self.kinds.get(&cell.as_ivec3()).copied().unwrap_or(TerrainKind::Ground)
+    }
+
+    pub fn clear(&mut self, cell: UVec3) {
+        self.kinds.remove(&cell.as_ivec3());
+    }
+
+    pub fn set(&mut self, cell: UVec3, kind: TerrainKind) {
+        self.kinds.insert(cell.as_ivec3(), kind);
+    }
+
+    pub fn nav(&self, cell: UVec3) -> Nav {
+        Nav::Passable(self.kind(cell).cost())
+    }
+
+    pub fn dig(&mut self, cell: UVec3) {
+        self.dug.insert(cell.as_ivec3());
+    }
+
+    // rock that was never dug out. walls, rubble and the surface don't have a rock kind, they dig
+    // like anything else and drop nothing
+    pub fn is_natural_rock(&self, cell: UVec3) -> bool {
+        cell.z > 0 && !self.dug.contains(&cell.as_ivec3())
+    }
+}
+
+// a planned road or floor, laid down by a worker
+#[derive(Debug, Component)]
+pub struct Paving {
+    pub kind: TerrainKind,
+    pub cell: UVec3,
+    pub built: bool
+}
+
+pub struct Terrain;
+
+impl Plugin for Terrain {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TerrainMap::default());
+
+        app.add_systems(Update, (seed_terrain, place_paving, show_built_paving));
+    }
+}
+
+const PAVING_Z_AXIS: f32 = 1.5;
+
+// mud along the shores, then the surface of a freshly streamed chunk opens up with the cost of its
+// ground. the levels under it stay rock. the mud goes again with a paged out chunk, it's generated
+// the same next time
+fn seed_terrain(
+    world_gen: Res<WorldGen>,
+    mut terrain: ResMut<TerrainMap>,
+    mut edits: ResMut<NavEdits>,
+    mut loaded: EventReader<NavChunkLoaded>,
+    mut unloaded: EventReader<NavChunkUnloaded>,
+) {
+    for NavChunkUnloaded(chunk) in unloaded.read() {
+        for column in chunk_cells(*chunk) { terrain.clear(column.extend(0)); }
+    }
+
+    for NavChunkLoaded(chunk) in loaded.read() {
+        for column in chunk_cells(*chunk) {
+            let surface = column.extend(0);
+            if (-0.4..-0.3).contains(&world_gen.value.height(column.as_ivec2())) {
+                terrain.set(surface, TerrainKind::Mud);
+            }
+            edits.stream(surface, terrain.nav(surface));
+        }
+    }
+}
+
+fn place_paving(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    items: Res<ItemRegistry>,
+    fog: Res<FogOfWar>,
+    terrain: Res<TerrainMap>,
+    mut inventory: ResMut<PlayerInventory>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    let Some(Placeable::Paving(kind)) = items.get(item).placeable else { return; };
+
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+
+    if !matches!(grid.nav(cell), Some(Nav::Passable(_))) { return; }
+    if terrain.kind(cell) == kind || occupancy.is_taken(cell) { return; }
+    if !inventory.take_one(item) { return; }
+
+    commands.spawn((
+        Name::new(format!("{:?}", kind)),
+        Paving { kind, cell, built: false },
+        Level(cell.z),
+        Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, PAVING_Z_AXIS),
+        Sprite { color: items.get(item).icon.with_alpha(0.25), custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)), ..default() }
+    ));
+}
+
+// a new floor over an old one takes its place once it's built
+fn show_built_paving(
+    mut commands: Commands,
+    mut pavings: Query<(Entity, &Paving, &mut Sprite), Changed<Paving>>,
+    all: Query<(Entity, &Paving)>,
+) {
+    for (entity, paving, mut sprite) in &mut pavings {
+        if !paving.built { continue; }
+        sprite.color = sprite.color.with_alpha(0.7);
+        for (old, _) in all.iter().filter(|(old, p)| *old != entity && p.cell == paving.cell && p.built) {
+            commands.entity(old).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_undug_cave_rock_is_natural() {
+        let mut terrain = TerrainMap::default();
+        terrain.dig(UVec3::new(2, 2, 1));
+
+        assert!(terrain.is_natural_rock(UVec3::new(3, 2, 1)));
+        assert!(!terrain.is_natural_rock(UVec3::new(2, 2, 1)));
+        assert!(!terrain.is_natural_rock(UVec3::new(3, 2, 0)));
+    }
+}
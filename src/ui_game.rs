@@ -1,24 +1,37 @@
 use bevy::prelude::*;
 
-use crate::player::{CanPlayerBuild, Object, ObjectSelected, ObjectStack, PlayerInventory};
+use crate::{item::ItemRegistry, player::{CanPlayerBuild, Held, ObjectSelected, PlayerInventory, Tool}, world::{ViewLevel, LEVELS}};
 
 #[derive(Debug, Component)]
-struct UiInventorySlot { slot: ObjectStack }
+struct UiInventorySlot { held: Held }
 
 #[derive(Debug, Component)]
 struct UiSlot;
 
+#[derive(Debug, Component)]
+struct UiSlotCount;
+
+// steps the viewed level, 1 goes deeper and -1 back up towards the surface
+#[derive(Debug, Component)]
+struct LevelButton(i32);
+
+#[derive(Debug, Component)]
+struct LevelLabel;
+
 pub struct GameUi;
 
 impl Plugin for GameUi {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup);
 
-        app.add_systems(Update, (can_player_interact, ui_slot_selection, ui_load_items, log_slots));        
+        app.add_systems(Update, (can_player_interact, ui_slot_selection, ui_load_items, ui_slot_visuals, log_slots));
+        app.add_systems(Update, (level_buttons, level_keys, update_level_label));
     }
 }
 
 const WINDOWRESOLUTION: (f32, f32) = (800.0, 600.0);
+const EMPTY_SLOT_COLOR: Color = Color::srgb(0.92, 0.92, 0.92);
+const ACTION_SLOT_COLOR: Color = Color::srgb(0.95, 0.85, 0.4);
 
 fn setup(mut commands: Commands) {
     commands.spawn(
@@ -45,7 +58,13 @@ fn setup(mut commands: Commands) {
                 }, 
                 BackgroundColor(Color::srgb(0.27, 0.27, 0.27)),
                 Button
-            ));
+            )).with_children(|level_selector| {
+                level_selector.spawn((Node { padding:UiRect::horizontal(Val::Px(6.)), ..default() }, Button, LevelButton(-1)))
+                    .with_child((Text::new("up"), TextFont { font_size: 12.0, ..default() }));
+                level_selector.spawn((Text::new("surface"), TextFont { font_size: 12.0, ..default() }, LevelLabel));
+                level_selector.spawn((Node { padding:UiRect::horizontal(Val::Px(6.)), ..default() }, Button, LevelButton(1)))
+                    .with_child((Text::new("down"), TextFont { font_size: 12.0, ..default() }));
+            });
         });
 
         main.spawn(
@@ -60,7 +79,7 @@ fn setup(mut commands: Commands) {
             game_screen.spawn(
                 Node {
                     width:Val::Percent(100.0),
-                    height:Val::Percent(90.0),
+                    height:Val::Percent(84.0),
                     display:Display::Flex,
                     flex_direction:FlexDirection::Row,
                     ..default()
@@ -70,7 +89,7 @@ fn setup(mut commands: Commands) {
             game_screen.spawn(
                 Node {
                     width:Val::Percent(100.0),
-                    height:Val::Percent(10.0),
+                    height:Val::Percent(16.0),
                     display:Display::Flex,
                     flex_direction:FlexDirection::Row,
                     justify_content:JustifyContent::Center,
@@ -80,10 +99,11 @@ fn setup(mut commands: Commands) {
             ).with_children(|access_bar|{
                 access_bar.spawn((
                     Node {
-                        width:Val::Px(320.0),
-                        height:Val::Px(48.0),
+                        width:Val::Px(440.0),
+                        height:Val::Px(88.0),
                         display:Display::Flex,
                         column_gap:Val::Px(4.),
+                        row_gap:Val::Px(4.),
                         flex_wrap:FlexWrap::Wrap,
                         align_items:AlignItems::Center,
                         flex_direction:FlexDirection::Row,
@@ -92,8 +112,9 @@ fn setup(mut commands: Commands) {
                     },BackgroundColor(Color::srgb(0.4,0.4,0.90)),
                     Button
                 )).with_children(|object_slots|{
-                    for _ in 0..6 {
-                        object_slots.spawn(build_item_slot());
+                    object_slots.spawn(build_item_slot(Held::Tool(Tool::Action)));
+                    for _ in 0..19 {
+                        object_slots.spawn(build_item_slot(Held::Tool(Tool::None)));
                     }
                 });
             });
@@ -101,19 +122,22 @@ fn setup(mut commands: Commands) {
     });
 }
 
-fn build_item_slot() -> impl Bundle {
+fn build_item_slot(held: Held) -> impl Bundle {
     (
         Node {
             width:Val::Px(40.0),
             height:Val::Px(40.0),
             display:Display::Flex,
             flex_direction:FlexDirection::Row,
+            align_items:AlignItems::FlexEnd,
+            justify_content:JustifyContent::FlexEnd,
             ..default()
         },
-        UiInventorySlot { slot: ObjectStack { item:Object::None, total:0, assigned:false, related_entity:Entity::from_raw(0) }},
-        BackgroundColor(Color::srgb(0.92, 0.92, 0.92)),
+        UiInventorySlot { held },
+        BackgroundColor(EMPTY_SLOT_COLOR),
         Button,
-        UiSlot
+        UiSlot,
+        children![(Text::new(""), TextFont { font_size: 10.0, ..default() }, TextColor(Color::BLACK), UiSlotCount)]
     )
 }
 
@@ -124,28 +148,85 @@ fn can_player_interact(mut can_build: ResMut<CanPlayerBuild>, interactions: Quer
 
 fn ui_slot_selection(mut item_selected: ResMut<ObjectSelected>,inventory_slots: Query<(&Interaction,&UiInventorySlot,Entity),(With<UiSlot>, Changed<Interaction>)>) {
     for (interaction, ui_slot, entity) in &inventory_slots {
-        if *interaction == Interaction::Pressed { (item_selected.object, item_selected.ui_entity) = (ui_slot.slot.item, entity); println!("{:?}", item_selected); }
+        if *interaction == Interaction::Pressed { (item_selected.held, item_selected.ui_entity) = (ui_slot.held, entity); println!("{:?}", item_selected); }
     }
 }
 
-// sync the inventory items and the ui
+// sync the inventory items and the ui, one slot per item kind
 fn ui_load_items(mut ui_inventory_slots: Query<(&mut UiInventorySlot, Entity), With<UiSlot>>,mut player_inventory: ResMut<PlayerInventory>) {
     let Some(item_not_assigned) = player_inventory.stacks.iter_mut().find(|i|!i.assigned) else { return; };
+    let held = Held::Item(item_not_assigned.item);
 
-    if let Some(mut slot) = ui_inventory_slots.iter_mut().find(|s| s.0.slot.item == Object::None) {
-        slot.0.slot.assigned = true;
-        slot.0.slot.item = item_not_assigned.item;
-        slot.0.slot.total = item_not_assigned.total;
+    let slot = match ui_inventory_slots.iter_mut().find(|s| s.0.held == held) {
+        Some(slot) => Some(slot),
+        None => ui_inventory_slots.iter_mut().find(|s| s.0.held == Held::Tool(Tool::None))
+    };
+
+    if let Some(mut slot) = slot {
+        slot.0.held = held;
 
         item_not_assigned.assigned = true; // flag to not assign it again!
         item_not_assigned.related_entity = slot.1; // assign the entity id of the ui slot to later decrease it
     }
 }
 
+// colors and counts come from the item registry and the inventory, empty item slots are released
+fn ui_slot_visuals(
+    items: Res<ItemRegistry>,
+    player_inventory: Res<PlayerInventory>,
+    mut item_selected: ResMut<ObjectSelected>,
+    mut ui_inventory_slots: Query<(&mut UiInventorySlot, &mut BackgroundColor), With<UiSlot>>,
+    mut counts: Query<(&mut Text, &ChildOf), With<UiSlotCount>>,
+) {
+    for (mut slot, mut background) in &mut ui_inventory_slots {
+        if let Held::Item(item) = slot.held && player_inventory.count(item) == 0 {
+            if item_selected.held == slot.held { item_selected.held = Held::Tool(Tool::Action); }
+            slot.held = Held::Tool(Tool::None);
+        }
+
+        background.0 = match slot.held {
+            Held::Tool(Tool::None) => EMPTY_SLOT_COLOR,
+            Held::Tool(Tool::Action) => ACTION_SLOT_COLOR,
+            Held::Item(item) => items.get(item).icon
+        };
+    }
+
+    for (mut text, child_of) in &mut counts {
+        let Ok((slot, _)) = ui_inventory_slots.get(child_of.parent()) else { continue; };
+        text.0 = match slot.held {
+            Held::Item(item) => player_inventory.count(item).to_string(),
+            Held::Tool(_) => String::new()
+        };
+    }
+}
+
 fn log_slots(input: Res<ButtonInput<KeyCode>>, ui_inventory_slots: Query<(&UiInventorySlot, Entity), With<UiSlot>>) {
     if input.just_pressed(KeyCode::KeyP) {
         for slot in &ui_inventory_slots {
             println!("{:?}", slot.0);
         }
     }
-}
\ No newline at end of file
+}
+
+fn step_level(view: &mut ResMut<ViewLevel>, step: i32) {
+    let level = (view.0 as i32 + step).clamp(0, LEVELS as i32 - 1) as u32;
+    if level != view.0 { view.0 = level; }
+}
+
+fn level_buttons(mut view: ResMut<ViewLevel>, buttons: Query<(&Interaction, &LevelButton), Changed<Interaction>>) {
+    for (interaction, button) in &buttons {
+        if *interaction == Interaction::Pressed { step_level(&mut view, button.0); }
+    }
+}
+
+fn level_keys(input: Res<ButtonInput<KeyCode>>, mut view: ResMut<ViewLevel>) {
+    if input.just_pressed(KeyCode::PageUp) { step_level(&mut view, -1); }
+    if input.just_pressed(KeyCode::PageDown) { step_level(&mut view, 1); }
+}
+
+fn update_level_label(view: Res<ViewLevel>, mut labels: Query<&mut Text, With<LevelLabel>>) {
+    if !view.is_changed() { return; }
+    for mut text in &mut labels {
+        text.0 = if view.0 == 0 { "surface".to_string() } else { format!("level {}", view.0) };
+    }
+}
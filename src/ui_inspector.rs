@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy_northstar::prelude::{AgentPos, Pathfind};
+
+use crate::{job::{stop_agent, CurrentJob, JobBoard, WorkPriorities}, needs::Needs, player::SelectedEntities, skills::{Skill, Skills}};
+
+#[derive(Debug, Component)]
+struct InspectorRoot { worker: Entity }
+
+#[derive(Debug, Component)]
+struct InspectorText;
+
+#[derive(Debug, Component)]
+struct CancelJobButton;
+
+#[derive(Debug, Component)]
+struct JobPriorityButton;
+
+pub struct InspectorUi;
+
+impl Plugin for InspectorUi {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (rebuild_inspector, update_inspector_text, cancel_job_pressed, job_priority_pressed));
+    }
+}
+
+const BUTTON_COLOR: Color = Color::srgb(0.27, 0.27, 0.27);
+
+// the inspector only shows while exactly one worker is selected
+fn inspected_worker(selected: &SelectedEntities, workers: &Query<(), With<AgentPos>>) -> Option<Entity> {
+    if selected.entities.len() != 1 { return None; }
+    selected.entities.iter().copied().find(|e| workers.contains(*e))
+}
+
+fn rebuild_inspector(
+    mut commands: Commands,
+    selected: Res<SelectedEntities>,
+    workers: Query<(), With<AgentPos>>,
+    roots: Query<(Entity, &InspectorRoot)>,
+) {
+    let worker = inspected_worker(&selected, &workers);
+    if roots.iter().map(|(_, r)| r.worker).next() == worker { return; }
+
+    for (root, _) in &roots { commands.entity(root).despawn(); }
+    let Some(worker) = worker else { return; };
+
+    commands.spawn((
+        Node {
+            width:Val::Px(180.0),
+            position_type:PositionType::Absolute,
+            right:Val::Px(8.0),
+            bottom:Val::Px(100.0),
+            display:Display::Flex,
+            flex_direction:FlexDirection::Column,
+            row_gap:Val::Px(4.),
+            padding:UiRect::all(Val::Px(6.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.18, 0.18)),
+        Button,
+        InspectorRoot { worker }
+    )).with_children(|panel| {
+        panel.spawn((Text::new(""), TextFont { font_size: 11.0, ..default() }, InspectorText));
+
+        panel.spawn(Node { display:Display::Flex, flex_direction:FlexDirection::Row, column_gap:Val::Px(4.), ..default() })
+            .with_children(|buttons| {
+                buttons.spawn((
+                    Node { padding:UiRect::axes(Val::Px(6.), Val::Px(2.)), ..default() },
+                    BackgroundColor(BUTTON_COLOR),
+                    Button,
+                    CancelJobButton
+                )).with_child((Text::new("cancel job"), TextFont { font_size: 11.0, ..default() }));
+
+                buttons.spawn((
+                    Node { padding:UiRect::axes(Val::Px(6.), Val::Px(2.)), ..default() },
+                    BackgroundColor(BUTTON_COLOR),
+                    Button,
+                    JobPriorityButton
+                )).with_child((Text::new("priority"), TextFont { font_size: 11.0, ..default() }));
+            });
+    });
+}
+
+fn update_inspector_text(
+    board: Res<JobBoard>,
+    roots: Query<&InspectorRoot>,
+    workers: Query<(&Name, &AgentPos, Option<&CurrentJob>, Option<&Pathfind>, Option<&Needs>, Option<&Skills>, Option<&WorkPriorities>)>,
+    mut texts: Query<&mut Text, With<InspectorText>>,
+) {
+    let Some(root) = roots.iter().next() else { return; };
+    let Ok((name, agent_pos, job, pathfind, needs, skills, priorities)) = workers.get(root.worker) else { return; };
+
+    let mut lines = vec![
+        format!("worker {}", name.as_str()),
+        format!("position {} {} {}", agent_pos.0.x, agent_pos.0.y, agent_pos.0.z)
+    ];
+
+    match job {
+        Some(job) => {
+            let priority = priorities.map(|p| p.get(job.kind)).filter(|_| job.order.and_then(|id| board.get(id)).is_some());
+            lines.push(format!("job {:?} at {} {} ({:.0}%)", job.kind, job.target.x, job.target.y, 100.0 * (job.progress / job.kind.work()).min(1.0)));
+            if let Some(priority) = priority { lines.push(format!("job priority {}", priority)); }
+        },
+        None => lines.push("job idle".to_string())
+    }
+
+    match pathfind {
+        Some(pathfind) if pathfind.goal != agent_pos.0 => lines.push(format!("heading to {} {}", pathfind.goal.x, pathfind.goal.y)),
+        _ => lines.push("heading nowhere".to_string())
+    }
+
+    if let Some(needs) = needs {
+        lines.push(format!("hunger {:.0}%  rest {:.0}%", needs.hunger * 100.0, needs.rest * 100.0));
+    }
+
+    if let Some(skills) = skills {
+        for skill in Skill::ALL {
+            lines.push(format!("{:?} lv {} ({:.0}%)", skill, skills.level(skill), skills.progress(skill) * 100.0));
+        }
+    }
+
+    for mut text in &mut texts { text.0 = lines.join("\n"); }
+}
+
+// the order goes back on the board, held back for a while so the same worker doesn't grab it again
+fn cancel_job_pressed(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut board: ResMut<JobBoard>,
+    buttons: Query<&Interaction, (With<CancelJobButton>, Changed<Interaction>)>,
+    roots: Query<&InspectorRoot>,
+    jobs: Query<&CurrentJob>,
+) {
+    if !buttons.iter().any(|i| *i == Interaction::Pressed) { return; }
+    let Some(root) = roots.iter().next() else { return; };
+    let Ok(job) = jobs.get(root.worker) else { return; };
+
+    if let Some(order) = job.order { board.defer(order, time.elapsed_secs()); }
+    stop_agent(&mut commands, root.worker);
+    commands.entity(root.worker).remove::<CurrentJob>();
+}
+
+// cycles how much this worker wants the kind of job it is doing
+fn job_priority_pressed(
+    buttons: Query<&Interaction, (With<JobPriorityButton>, Changed<Interaction>)>,
+    roots: Query<&InspectorRoot>,
+    mut workers: Query<(&CurrentJob, &mut WorkPriorities)>,
+) {
+    if !buttons.iter().any(|i| *i == Interaction::Pressed) { return; }
+    let Some(root) = roots.iter().next() else { return; };
+    let Ok((job, mut priorities)) = workers.get_mut(root.worker) else { return; };
+
+    if !job.kind.is_need() { priorities.cycle(job.kind); }
+}
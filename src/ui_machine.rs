@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::{machine::{Machine, SelectedMachine}, player::Stockpile, recipe::{RecipeId, RecipeRegistry}};
+
+#[derive(Debug, Component)]
+struct RecipePanel;
+
+#[derive(Debug, Component)]
+struct RecipeButton { machine: Entity, recipe: RecipeId }
+
+pub struct MachineUi;
+
+impl Plugin for MachineUi {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (rebuild_recipe_panel, recipe_button_pressed, recipe_button_colors));
+    }
+}
+
+const RECIPE_IDLE_COLOR: Color = Color::srgb(0.27, 0.27, 0.27);
+const RECIPE_ACTIVE_COLOR: Color = Color::srgb(0.4, 0.4, 0.90);
+
+// one panel for the selected machine, listing every recipe its machine type can run
+fn rebuild_recipe_panel(
+    mut commands: Commands,
+    selected: Res<SelectedMachine>,
+    registry: Res<RecipeRegistry>,
+    machines: Query<&Machine>,
+    panels: Query<Entity, With<RecipePanel>>,
+) {
+    if !selected.is_changed() { return; }
+
+    for panel in &panels { commands.entity(panel).despawn(); }
+
+    let Some(machine_entity) = selected.entity else { return; };
+    let Ok(machine) = machines.get(machine_entity) else { return; };
+
+    commands.spawn((
+        Node {
+            width:Val::Px(180.0),
+            position_type:PositionType::Absolute,
+            right:Val::Px(8.0),
+            top:Val::Px(40.0),
+            display:Display::Flex,
+            flex_direction:FlexDirection::Column,
+            row_gap:Val::Px(4.),
+            padding:UiRect::all(Val::Px(6.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.18, 0.18)),
+        Button,
+        RecipePanel
+    )).with_children(|panel| {
+        panel.spawn((Text::new(format!("{:?}", machine.kind)), TextFont { font_size: 14.0, ..default() }));
+
+        for (id, recipe) in registry.for_machine(machine.kind) {
+            panel.spawn((
+                Node {
+                    width:Val::Percent(100.0),
+                    height:Val::Px(22.0),
+                    align_items:AlignItems::Center,
+                    padding:UiRect::horizontal(Val::Px(4.)),
+                    ..default()
+                },
+                BackgroundColor(RECIPE_IDLE_COLOR),
+                Button,
+                RecipeButton { machine: machine_entity, recipe: id }
+            )).with_child((Text::new(recipe.name.clone()), TextFont { font_size: 12.0, ..default() }));
+        }
+    });
+}
+
+// switching recipes stops the batch in progress, its inputs go back to the stockpile
+fn recipe_button_pressed(
+    buttons: Query<(&Interaction, &RecipeButton), Changed<Interaction>>,
+    registry: Res<RecipeRegistry>,
+    mut stockpile: ResMut<Stockpile>,
+    mut machines: Query<&mut Machine>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed { continue; }
+        let Ok(mut machine) = machines.get_mut(button.machine) else { continue; };
+        if machine.recipe == Some(button.recipe) { continue; }
+
+        if machine.progress.is_some() && let Some(running) = machine.recipe.and_then(|id| registry.get(id)) {
+            stockpile.items.add_all(&running.inputs);
+        }
+        machine.recipe = Some(button.recipe);
+        machine.progress = None;
+    }
+}
+
+fn recipe_button_colors(machines: Query<&Machine>, mut buttons: Query<(&RecipeButton, &mut BackgroundColor)>) {
+    for (button, mut color) in &mut buttons {
+        let active = machines.get(button.machine).is_ok_and(|m| m.recipe == Some(button.recipe));
+        color.0 = if active { RECIPE_ACTIVE_COLOR } else { RECIPE_IDLE_COLOR };
+    }
+}
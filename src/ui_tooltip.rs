@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use bevy_northstar::nav::Nav;
+
+use crate::{fog::FogOfWar, item::ItemRegistry, job::pick_tier, mouse::GridClicked, player::Stockpile, terrain::TerrainMap, world::WorldGrid, worldgen::WorldGen};
+
+#[derive(Debug, Component)]
+struct TileTooltip;
+
+pub struct TooltipUi;
+
+impl Plugin for TooltipUi {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_tooltip);
+        app.add_systems(Update, update_tooltip);
+    }
+}
+
+const CURSOR_OFFSET: f32 = 14.0;
+
+fn setup_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type:PositionType::Absolute,
+            padding:UiRect::axes(Val::Px(5.), Val::Px(3.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.85)),
+        Text::new(""),
+        TextFont { font_size: 11.0, ..default() },
+        Visibility::Hidden,
+        GlobalZIndex(10),
+        TileTooltip
+    ));
+}
+
+// explored rock under the cursor shows what it is, how hard it is and whether the colony can dig it yet
+fn update_tooltip(
+    window: Single<&Window>,
+    grid: Single<&WorldGrid>,
+    tooltip: Single<(&mut Node, &mut Text, &mut Visibility), With<TileTooltip>>,
+    hovered: Res<GridClicked>,
+    fog: Res<FogOfWar>,
+    terrain: Res<TerrainMap>,
+    world_gen: Res<WorldGen>,
+    stockpile: Res<Stockpile>,
+    items: Res<ItemRegistry>,
+) {
+    let (mut node, mut text, mut visibility) = tooltip.into_inner();
+    let cell = hovered.position;
+
+    let rock = fog.is_explored(cell) && terrain.is_natural_rock(cell) && matches!(grid.nav(cell), Some(Nav::Impassable));
+    let Some(cursor) = window.cursor_position().filter(|_| rock) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let kind = world_gen.value.rock(cell.as_ivec3());
+    let mut lines = vec![kind.name().to_string(), format!("hardness {:.1}", kind.hardness())];
+    if kind.tier() > pick_tier(&stockpile, &items) { lines.push(format!("needs a tier {} pick", kind.tier())); }
+
+    let lines = lines.join("\n");
+    if text.0 != lines { text.0 = lines; }
+    node.left = Val::Px(cursor.x + CURSOR_OFFSET);
+    node.top = Val::Px(cursor.y + CURSOR_OFFSET);
+    *visibility = Visibility::Inherited;
+}
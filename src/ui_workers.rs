@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+
+use crate::{job::{JobKind, WorkPriorities, DEFAULT_PRIORITY, LOWEST_PRIORITY}, skills::Skills};
+
+#[derive(Resource)]
+struct WorkPanel {
+    open: bool,
+    sort: Option<JobKind>, // none sorts by name
+    rows: usize
+}
+
+#[derive(Debug, Component)]
+struct WorkPanelRoot;
+
+#[derive(Debug, Component)]
+struct PriorityCell { worker: Entity, kind: JobKind }
+
+#[derive(Debug, Component)]
+struct SortButton(Option<JobKind>);
+
+#[derive(Debug, Component)]
+struct ColumnToggle(JobKind);
+
+pub struct WorkersUi;
+
+impl Plugin for WorkersUi {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WorkPanel { open: false, sort: None, rows: 0 });
+
+        app.add_systems(Update, (toggle_work_panel, rebuild_work_panel).chain());
+        app.add_systems(Update, (priority_cell_pressed, sort_button_pressed, column_toggle_pressed, update_priority_cells));
+    }
+}
+
+const CELL_WIDTH: f32 = 52.0;
+const NAME_WIDTH: f32 = 60.0;
+const ROW_HEIGHT: f32 = 20.0;
+const HEADER_COLOR: Color = Color::srgb(0.27, 0.27, 0.27);
+const CELL_OFF_COLOR: Color = Color::srgb(0.22, 0.22, 0.22);
+
+fn priority_color(priority: u8) -> Color {
+    match priority {
+        1 => Color::srgb(0.3, 0.75, 0.35),
+        2 => Color::srgb(0.55, 0.7, 0.3),
+        3 => Color::srgb(0.7, 0.62, 0.3),
+        4 => Color::srgb(0.6, 0.45, 0.3),
+        _ => CELL_OFF_COLOR
+    }
+}
+
+fn toggle_work_panel(input: Res<ButtonInput<KeyCode>>, mut panel: ResMut<WorkPanel>) {
+    if input.just_pressed(KeyCode::KeyJ) { panel.open = !panel.open; }
+}
+
+fn cell_node(width: f32) -> Node {
+    Node {
+        width:Val::Px(width),
+        height:Val::Px(ROW_HEIGHT),
+        align_items:AlignItems::Center,
+        justify_content:JustifyContent::Center,
+        ..default()
+    }
+}
+
+fn row_node() -> Node {
+    Node { display:Display::Flex, flex_direction:FlexDirection::Row, column_gap:Val::Px(2.), ..default() }
+}
+
+fn label(text: impl Into<String>) -> impl Bundle {
+    (Text::new(text), TextFont { font_size: 11.0, ..default() })
+}
+
+// one row per worker, one column per board job
+fn rebuild_work_panel(
+    mut commands: Commands,
+    mut panel: ResMut<WorkPanel>,
+    workers: Query<(Entity, &Name, &WorkPriorities, Option<&Skills>)>,
+    roots: Query<Entity, With<WorkPanelRoot>>,
+) {
+    let rows = workers.iter().count();
+    if !panel.is_changed() && panel.rows == rows { return; }
+    panel.bypass_change_detection().rows = rows;
+
+    for root in &roots { commands.entity(root).despawn(); }
+    if !panel.open { return; }
+
+    let mut sorted: Vec<(Entity, &Name, &WorkPriorities, Option<&Skills>)> = workers.iter().collect();
+    match panel.sort {
+        None => sorted.sort_by(|a, b| a.1.as_str().cmp(b.1.as_str())),
+        Some(kind) => {
+            // priority 1 first and disabled last, higher skill first within the same priority
+            let key = |p: &WorkPriorities| if p.get(kind) == 0 { LOWEST_PRIORITY + 1 } else { p.get(kind) };
+            let level = |s: Option<&Skills>| kind.skill().zip(s).map_or(0, |(skill, s)| s.level(skill));
+            sorted.sort_by(|a, b| key(a.2).cmp(&key(b.2)).then(level(b.3).cmp(&level(a.3))));
+        }
+    }
+
+    commands.spawn((
+        Node {
+            position_type:PositionType::Absolute,
+            left:Val::Px(8.0),
+            top:Val::Px(40.0),
+            display:Display::Flex,
+            flex_direction:FlexDirection::Column,
+            row_gap:Val::Px(2.),
+            padding:UiRect::all(Val::Px(6.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.18, 0.18)),
+        Button,
+        WorkPanelRoot
+    )).with_children(|panel_node| {
+        panel_node.spawn(row_node()).with_children(|header| {
+            header.spawn((cell_node(NAME_WIDTH), BackgroundColor(HEADER_COLOR), Button, SortButton(None)))
+                .with_child(label("worker"));
+            for kind in JobKind::BOARD {
+                header.spawn((cell_node(CELL_WIDTH), BackgroundColor(HEADER_COLOR), Button, SortButton(Some(kind))))
+                    .with_child(label(format!("{:?}", kind)));
+            }
+        });
+
+        panel_node.spawn(row_node()).with_children(|toggles| {
+            toggles.spawn(cell_node(NAME_WIDTH)).with_child(label("all"));
+            for kind in JobKind::BOARD {
+                toggles.spawn((cell_node(CELL_WIDTH), BackgroundColor(HEADER_COLOR), Button, ColumnToggle(kind)))
+                    .with_child(label("on/off"));
+            }
+        });
+
+        for (worker, name, _, _) in sorted {
+            panel_node.spawn(row_node()).with_children(|row| {
+                row.spawn(cell_node(NAME_WIDTH)).with_child(label(name.as_str()));
+                for kind in JobKind::BOARD {
+                    row.spawn((cell_node(CELL_WIDTH), BackgroundColor(CELL_OFF_COLOR), Button, PriorityCell { worker, kind }))
+                        .with_child(label(""));
+                }
+            });
+        }
+    });
+}
+
+fn priority_cell_pressed(cells: Query<(&Interaction, &PriorityCell), Changed<Interaction>>, mut workers: Query<&mut WorkPriorities>) {
+    for (interaction, cell) in &cells {
+        if *interaction != Interaction::Pressed { continue; }
+        if let Ok(mut priorities) = workers.get_mut(cell.worker) { priorities.cycle(cell.kind); }
+    }
+}
+
+fn sort_button_pressed(buttons: Query<(&Interaction, &SortButton), Changed<Interaction>>, mut panel: ResMut<WorkPanel>) {
+    for (interaction, button) in &buttons {
+        if *interaction == Interaction::Pressed { panel.sort = button.0; }
+    }
+}
+
+// switches the whole column off, or back on at the default priority when it is already off for everyone
+fn column_toggle_pressed(buttons: Query<(&Interaction, &ColumnToggle), Changed<Interaction>>, mut workers: Query<&mut WorkPriorities>) {
+    for (interaction, toggle) in &buttons {
+        if *interaction != Interaction::Pressed { continue; }
+
+        let all_off = workers.iter().all(|p| p.get(toggle.0) == 0);
+        let priority = if all_off { DEFAULT_PRIORITY } else { 0 };
+        for mut priorities in &mut workers { priorities.set(toggle.0, priority); }
+    }
+}
+
+fn update_priority_cells(
+    workers: Query<&WorkPriorities>,
+    mut cells: Query<(&PriorityCell, &mut BackgroundColor, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (cell, mut background, children) in &mut cells {
+        let Ok(priorities) = workers.get(cell.worker) else { continue; };
+        let priority = priorities.get(cell.kind);
+
+        background.0 = priority_color(priority);
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = if priority == 0 { String::new() } else { priority.to_string() };
+            }
+        }
+    }
+}
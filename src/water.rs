@@ -0,0 +1,289 @@
+use bevy::{platform::collections::{HashMap, HashSet}, prelude::*};
+use bevy_northstar::{nav::Nav, prelude::AgentPos};
+
+use crate::{fog::FogOfWar, item::{ItemRegistry, Placeable}, light::LightMap, mouse::GridClicked, nav_edit::NavEdits, nav_stream::{chunk_cells, NavChunkLoaded, NavChunkUnloaded}, occupancy::Occupancy, player::{CanPlayerBuild, ObjectSelected, PlayerInventory}, power::{PowerConsumer, PowerNode}, stairs::Stairs, terrain::TerrainMap, world::{is_open, Level, TILESIZE, WorldGrid}, worldgen::WorldGen};
+
+// water depth per cell, 0..1. springs are the lakes from the terrain, they never run dry
+#[derive(Resource)]
+pub struct WaterMap {
+    pub levels: HashMap<IVec3, f32>,
+    springs: HashMap<IVec3, f32>,
+    flooded: HashSet<IVec3>, // cells only impassable because of the water on them
+    wet: HashSet<IVec3>, // cells the water may have put a cost on
+    flow: HashMap<IVec3, f32> // water that ran in or out of a cell in the last update
+}
+
+impl WaterMap {
+    pub fn level(&self, cell: UVec3) -> f32 {
+        self.levels.get(&cell.as_ivec3()).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_flooded(&self, cell: UVec3) -> bool {
+        self.flooded.contains(&cell.as_ivec3())
+    }
+
+    // water running past the four sides of a cell, still water doesn't count
+    pub fn flow_around(&self, cell: UVec3) -> f32 {
+        let cell = cell.as_ivec3();
+        [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y].iter()
+            .filter_map(|offset| self.flow.get(&(cell + *offset)))
+            .sum()
+    }
+}
+
+// drains the water around it while it has power
+#[derive(Debug, Component)]
+pub struct Pump { pub cell: UVec3 }
+
+#[derive(Resource)]
+struct WaterClock(Timer);
+
+pub struct Water;
+
+impl Plugin for Water {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WaterMap { levels: HashMap::new(), springs: HashMap::new(), flooded: HashSet::new(), wet: HashSet::new(), flow: HashMap::new() });
+        app.insert_resource(WaterClock(Timer::from_seconds(WATER_UPDATE_SECS, TimerMode::Repeating)));
+
+        app.add_systems(Update, (seed_springs, place_pump));
+        app.add_systems(Update, (flow_water, run_pumps, apply_water_costs).chain());
+    }
+}
+
+const PUMP_Z_AXIS: f32 = 3.0;
+const PUMP_DRAW: f32 = 20.0;
+const PUMP_RATE: f32 = 0.6; // depth drained per second at full power
+const PUMP_REACH: i32 = 2;
+const WATER_UPDATE_SECS: f32 = 0.25;
+const FLOW_RATE: f32 = 0.2; // share of the difference that moves to a lower neighbour per update
+const FALL_RATE: f32 = 0.5; // share of the water that drops to the level below per update
+const DRY_LEVEL: f32 = 0.02;
+const WADING_LEVEL: f32 = 0.2;
+const SWIMMING_LEVEL: f32 = 0.6;
+const WADING_COST: u32 = 5;
+
+// the deep and shallow water bands of the terrain, for chunks as they stream in. a chunk that is
+// paged out again never had anything done to it, its lakes go with it
+fn seed_springs(
+    world_gen: Res<WorldGen>,
+    mut water: ResMut<WaterMap>,
+    mut loaded: EventReader<NavChunkLoaded>,
+    mut unloaded: EventReader<NavChunkUnloaded>,
+) {
+    for NavChunkLoaded(chunk) in loaded.read() {
+        for column in chunk_cells(*chunk) {
+            let cell = column.as_ivec2().extend(0);
+            let level = match world_gen.value.height(cell.truncate()) {
+                n if n <= -0.6 => 1.0,
+                n if n <= -0.4 => 0.4,
+                _ => continue
+            };
+            water.springs.insert(cell, level);
+            water.levels.insert(cell, level);
+        }
+    }
+
+    for NavChunkUnloaded(chunk) in unloaded.read() {
+        for column in chunk_cells(*chunk) {
+            let cell = column.as_ivec2().extend(0);
+            water.springs.remove(&cell);
+            water.levels.remove(&cell);
+            water.wet.remove(&cell);
+        }
+    }
+}
+
+fn place_pump(
+    mut commands: Commands,
+    grid: Single<&WorldGrid>,
+    mut edits: ResMut<NavEdits>,
+    can_build: Res<CanPlayerBuild>,
+    grid_position: Res<GridClicked>,
+    input: Res<ButtonInput<MouseButton>>,
+    object_selected: Res<ObjectSelected>,
+    items: Res<ItemRegistry>,
+    fog: Res<FogOfWar>,
+    mut inventory: ResMut<PlayerInventory>,
+    occupancy: Occupancy,
+) {
+    if !input.just_pressed(MouseButton::Left) || !can_build.enabled { return; }
+    let Some(item) = object_selected.item() else { return; };
+    if items.get(item).placeable != Some(Placeable::Pump) { return; }
+
+    let cell = grid_position.position;
+    if !fog.is_explored(cell) { return; }
+
+    if !matches!(grid.nav(cell), Some(Nav::Passable(_))) { return; }
+    if occupancy.is_taken(cell) { return; }
+    if !inventory.take_one(item) { return; }
+    // the pump takes the cell, whatever shallow water was on it drains off on the next update
+    edits.set(cell, Nav::Impassable);
+
+    commands.spawn((
+        Name::new("Pump"),
+        Pump { cell },
+        Level(cell.z),
+        PowerNode { cell, reach: 1, network: None },
+        PowerConsumer::new(PUMP_DRAW),
+        Transform::from_xyz(cell.x as f32 * TILESIZE as f32, cell.y as f32 * TILESIZE as f32, PUMP_Z_AXIS),
+        Sprite { color: items.get(item).icon, custom_size: Some(Vec2::new(TILESIZE as f32, TILESIZE as f32)), ..default() }
+    ));
+}
+
+// water can sit on open ground and stairs that were dug out, on its own springs and on cells it
+// flooded. the untouched surface is higher ground
+fn holds_water(grid: &WorldGrid, light: &LightMap, water: &WaterMap, cell: IVec3) -> bool {
+    if cell.cmplt(IVec3::ZERO).any() { return false; }
+    if water.springs.contains_key(&cell) || water.flooded.contains(&cell) { return true; }
+    light.is_roofed(cell) && is_open(grid, cell)
+}
+
+// every wet cell pushes part of its difference to each lower neighbour, and drops part of its
+// water to the level below where there is a way down. flows are summed up first and applied
+// after, so the result doesn't depend on the order cells are visited in
+fn flow_water(
+    time: Res<Time>,
+    mut clock: ResMut<WaterClock>,
+    mut water: ResMut<WaterMap>,
+    grid: Single<&WorldGrid>,
+    light: Res<LightMap>,
+    stairs: Query<&Stairs>,
+) {
+    if !clock.0.tick(time.delta()).just_finished() { return; }
+    let grid = grid.into_inner();
+
+    // water pours down a staircase, and lake beds let it seep into caves dug out right under them
+    let ways_down: HashSet<IVec3> = stairs.iter()
+        .filter(|s| s.other.z > s.cell.z)
+        .map(|s| s.cell.as_ivec3())
+        .chain(water.springs.keys().copied())
+        .collect();
+
+    let mut deltas: HashMap<IVec3, f32> = HashMap::new();
+    let mut flows: HashMap<IVec3, f32> = HashMap::new();
+    for (cell, level) in water.levels.iter() {
+        if !holds_water(grid, &light, &water, *cell) {
+            *deltas.entry(*cell).or_insert(0.0) -= level;
+            continue;
+        }
+
+        for offset in [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y] {
+            let next = *cell + offset;
+            if !holds_water(grid, &light, &water, next) { continue; }
+            let difference = level - water.levels.get(&next).copied().unwrap_or(0.0);
+            if difference <= 0.0 { continue; }
+
+            let flow = difference * FLOW_RATE / 2.0;
+            *deltas.entry(*cell).or_insert(0.0) -= flow;
+            *deltas.entry(next).or_insert(0.0) += flow;
+            *flows.entry(*cell).or_insert(0.0) += flow;
+            *flows.entry(next).or_insert(0.0) += flow;
+        }
+
+        let below = *cell + IVec3::Z;
+        if !ways_down.contains(cell) || !holds_water(grid, &light, &water, below) { continue; }
+        let fall = (level * FALL_RATE).min(1.0 - water.levels.get(&below).copied().unwrap_or(0.0));
+        if fall <= 0.0 { continue; }
+
+        *deltas.entry(*cell).or_insert(0.0) -= fall;
+        *deltas.entry(below).or_insert(0.0) += fall;
+        *flows.entry(*cell).or_insert(0.0) += fall;
+        *flows.entry(below).or_insert(0.0) += fall;
+    }
+
+    // written around change detection, still water would otherwise count as changed every update
+    // and rewrite its costs to the grid
+    let mut changed = false;
+    let map = water.bypass_change_detection();
+    if map.flow != flows {
+        map.flow = flows;
+        changed = true;
+    }
+
+    for (cell, delta) in deltas {
+        let before = map.levels.get(&cell).copied().unwrap_or(0.0);
+        let level = (before + delta).min(1.0);
+        let level = if level < DRY_LEVEL { 0.0 } else { level };
+        if level == before { continue; }
+        if level == 0.0 { map.levels.remove(&cell); } else { map.levels.insert(cell, level); }
+        changed = true;
+    }
+
+    let springs: Vec<(IVec3, f32)> = map.springs.iter().map(|(c, l)| (*c, *l)).collect();
+    for (cell, level) in springs {
+        if map.levels.get(&cell) == Some(&level) { continue; }
+        map.levels.insert(cell, level);
+        changed = true;
+    }
+    if changed { water.set_changed(); }
+}
+
+// each pump drains the wettest cell in reach, as fast as its power allows
+fn run_pumps(
+    time: Res<Time>,
+    mut water: ResMut<WaterMap>,
+    pumps: Query<(&Pump, &PowerConsumer)>,
+) {
+    for (pump, power) in &pumps {
+        let center = pump.cell.as_ivec3();
+        let wettest = (-PUMP_REACH..=PUMP_REACH)
+            .flat_map(|x| (-PUMP_REACH..=PUMP_REACH).map(move |y| center + IVec3::new(x, y, 0)))
+            .filter_map(|cell| water.levels.get(&cell).map(|level| (cell, *level)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((cell, level)) = wettest else { continue; };
+
+        let level = level - PUMP_RATE * power.satisfaction * time.delta_secs();
+        if level < DRY_LEVEL { water.levels.remove(&cell); } else { water.levels.insert(cell, level); }
+    }
+}
+
+fn water_nav(level: f32, ground_cost: u32) -> Nav {
+    if level >= SWIMMING_LEVEL { Nav::Impassable }
+    else if level >= WADING_LEVEL { Nav::Passable(ground_cost.max(WADING_COST)) }
+    else { Nav::Passable(ground_cost) }
+}
+
+fn nav_cost(nav: Nav) -> u32 {
+    match nav { Nav::Passable(cost) => cost, _ => 0 }
+}
+
+// deep water blocks the cell and wading through shallow water is slow, on top of what the ground
+// underneath costs. only cells whose cost changed touch the grid, and a worker caught by rising
+// water can still wade out. the map itself is only written when a cell actually changes, anything
+// else would mark it changed every frame
+fn apply_water_costs(
+    grid: Single<&WorldGrid>,
+    mut edits: ResMut<NavEdits>,
+    mut water: ResMut<WaterMap>,
+    terrain: Res<TerrainMap>,
+    agents: Query<&AgentPos>,
+) {
+    if !water.is_changed() && !terrain.is_changed() { return; }
+
+    let mut cells: HashSet<IVec3> = water.levels.keys().copied().collect();
+    cells.extend(water.wet.iter().copied());
+
+    for cell in cells {
+        let grid_cell = cell.as_uvec3();
+        let level = water.levels.get(&cell).copied().unwrap_or(0.0);
+        let ground_cost = terrain.kind(grid_cell).cost();
+        let mut nav = water_nav(level, ground_cost);
+        if matches!(nav, Nav::Impassable) && agents.iter().any(|a| a.0 == grid_cell) { nav = Nav::Passable(ground_cost.max(WADING_COST)); }
+
+        // rock, buildings and stairs are left alone, water only changes cells it could flow into
+        let current = edits.nav(&grid, grid_cell);
+        let open = water.flooded.contains(&cell) || matches!(current, Some(Nav::Passable(_)));
+        let wet = open && level >= DRY_LEVEL;
+        if water.wet.contains(&cell) != wet {
+            if wet { water.wet.insert(cell); } else { water.wet.remove(&cell); }
+        }
+        if !open || current.map(nav_cost) == Some(nav_cost(nav)) { continue; }
+
+        // lakes are made again with their chunk and don't keep it around, water the colony let in does
+        if water.springs.contains_key(&cell) { edits.stream(grid_cell, nav); } else { edits.set(grid_cell, nav); }
+        let flooded = matches!(nav, Nav::Impassable);
+        if water.flooded.contains(&cell) != flooded {
+            if flooded { water.flooded.insert(cell); } else { water.flooded.remove(&cell); }
+        }
+    }
+}
@@ -1,158 +1,348 @@
-use bevy_northstar::{grid::GridSettingsBuilder, nav::Nav, prelude::DebugGridBuilder, CardinalGrid};
-use noise::{NoiseFn, Perlin};
-use bevy::{platform::collections::HashSet, prelude::*};
+use bevy_northstar::{filter::NoCornerCutting, grid::{Grid, GridSettingsBuilder}, nav::Nav, prelude::DebugGridBuilder};
+use bevy::{asset::RenderAssetUsages, platform::collections::{HashMap, HashSet}, prelude::*, render::mesh::{Indices, PrimitiveTopology}, tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task}};
 
-use crate::camera::MainCameraActualPosition;
+use crate::{camera::MainCamera, worldgen::{WorldGen, WorldKind}};
 
-#[derive(Debug, Component)]
-struct GridSquare;
+// one tile of a chunk, keeps its own grid cell and base color so overlays can tint it
+#[derive(Debug, Clone)]
+pub struct GridSquare { pub cell: IVec3, pub color: Color }
+
+// chunks to draw, from the viewport plus a margin. drawn chunks stay until they leave the wider
+// keep rect, so panning back and forth over a chunk border doesn't respawn them every frame.
+// zoomed far out nothing is detailed and the lod regions take over
+#[derive(Resource)]
+pub struct DesiredChunks{ pub chunks: HashSet<(i32,i32)>, pub view: IRect, pub keep: IRect, pub center: (i32, i32), pub detailed: bool }
 
-#[derive(Debug, Resource)]
-struct PerlinInstance { value: Perlin }
+// a drawn chunk, all of its tiles are a single mesh. tints are the colors the tiles are drawn
+// with, the mesh is rebuilt whenever the chunk changes
+#[derive(Debug, Component)]
+pub struct Chunk {
+    pub coords: (i32, i32),
+    pub tiles: Vec<GridSquare>,
+    pub tints: Vec<Color>
+}
 
-#[derive(Debug, Resource)]
-struct ChunkData { values: Vec<((i32,i32), Color)> }
+#[derive(Resource)]
+struct ChunkMaterial(Handle<ColorMaterial>);
 
+// every drawn chunk by its coords, and the chunks still being generated in the background.
+// dropping a pending task cancels it
 #[derive(Resource)]
-pub struct DesiredChunks{ pub chunks: HashSet<(i32,i32)> }
+pub struct ChunkIndex{ pub chunks: HashMap<(i32, i32), Entity>, pub pending: HashMap<(i32, i32), Task<Vec<GridSquare>>> }
 
+// the cave level the player is looking at, 0 is the surface
 #[derive(Resource)]
-pub struct LoadedChunks{ pub chunks: HashSet<(i32, i32)> }
+pub struct ViewLevel(pub u32);
 
-pub struct MyWorldPlugin;
+// level a world object is on, only the viewed level is drawn
+#[derive(Debug, Component)]
+pub struct Level(pub u32);
+
+// how workers step between cells, the four straight directions unless the game is built with the
+// `diagonal` feature, which lets them cut across open ground as well
+#[cfg(feature = "diagonal")]
+pub type WorldNeighborhood = bevy_northstar::prelude::OrdinalNeighborhood;
+#[cfg(not(feature = "diagonal"))]
+pub type WorldNeighborhood = bevy_northstar::prelude::CardinalNeighborhood;
+
+pub type WorldGrid = Grid<WorldNeighborhood>;
+
+// cells a worker can stand in, stairs included. anything off the grid counts as rock
+pub fn is_open(grid: &WorldGrid, cell: IVec3) -> bool {
+    if cell.cmplt(IVec3::ZERO).any() { return false; }
+    matches!(grid.nav(cell.as_uvec3()), Some(Nav::Passable(_) | Nav::Portal(_)))
+}
+
+// one open chunk on two levels for tests, with rock in the given cells
+#[cfg(test)]
+pub fn test_grid(rock: &[IVec3]) -> WorldGrid {
+    let settings = GridSettingsBuilder::new_3d(NAV_CHUNK_SIZE, NAV_CHUNK_SIZE, 2).chunk_size(NAV_CHUNK_SIZE).build();
+    let mut grid = WorldGrid::new(&settings);
+    for x in 0..NAV_CHUNK_SIZE {
+        for y in 0..NAV_CHUNK_SIZE {
+            for z in 0..2 { grid.set_nav(UVec3::new(x, y, z), Nav::Passable(1)); }
+        }
+    }
+    for cell in rock { grid.set_nav(cell.as_uvec3(), Nav::Impassable); }
+    grid.build();
+    grid
+}
+
+// the kind of world and its seed are picked when the world is created
+pub struct MyWorldPlugin { pub kind: WorldKind, pub seed: u32 }
+
+impl MyWorldPlugin {
+    // `--world caves --seed 42` on the command line, islands on seed 9 for anything not given
+    pub fn from_args(args: impl Iterator<Item = String>) -> MyWorldPlugin {
+        let mut plugin = MyWorldPlugin { kind: WorldKind::Islands, seed: 9 };
+        let args: Vec<String> = args.collect();
+        for pair in args.windows(2) {
+            match pair[0].as_str() {
+                "--world" => match WorldKind::from_name(&pair[1]) {
+                    Some(kind) => plugin.kind = kind,
+                    None => warn!("unknown world {}, making islands", pair[1])
+                },
+                "--seed" => match pair[1].parse() {
+                    Ok(seed) => plugin.seed = seed,
+                    Err(_) => warn!("invalid seed {}, using {}", pair[1], plugin.seed)
+                },
+                _ => {}
+            }
+        }
+        plugin
+    }
+}
 
 impl Plugin for MyWorldPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ChunkData { values: Vec::new() });
-        app.insert_resource(LoadedChunks{ chunks: HashSet::new() });
-        app.insert_resource(DesiredChunks{ chunks: HashSet::new() });
-        app.insert_resource(PerlinInstance{value:Perlin::new(9)});
+        app.insert_resource(ChunkIndex{ chunks: HashMap::new(), pending: HashMap::new() });
+        app.insert_resource(DesiredChunks{ chunks: HashSet::new(), view: IRect::default(), keep: IRect::default(), center: (0, 0), detailed: true });
+        app.insert_resource(WorldGen{value:self.kind.generator(self.seed)});
+        app.insert_resource(ViewLevel(0));
 
-        app.add_systems(Startup, (generate_path_grid, setup_grid_data.after(generate_path_grid)));
-        app.add_systems(Update, (generate_new_chunk_data, spawn_new_chunks, cleanup_distant_chunks));
+        app.add_systems(Startup, (generate_path_grid, setup_chunk_material));
+        app.add_systems(Update, (generate_new_chunk_data, cleanup_distant_chunks, redraw_view_level, queue_chunk_generation, spawn_generated_chunks, update_chunk_meshes).chain());
+        app.add_systems(Update, show_view_level);
     }
 }
 
-const CHUNK_SIZE: i32 = 4;
-const RENDER_DISTANCE: i32 = 1;
+pub const CHUNK_SIZE: i32 = 4;
+pub const RENDER_MARGIN: i32 = 1; // chunks drawn past the edge of the screen
+pub const KEEP_MARGIN: i32 = 2;
+const LOD_SCALE_OUT: f32 = 3.0; // zoom where the tiles give way to lod regions
+const LOD_SCALE_IN: f32 = 2.5; // and where they come back, lower so the switch doesn't flicker
+const MAX_PENDING_CHUNKS: usize = 8; // few enough that the closest chunks are always next
 
 pub const TILESIZE: i32 = 12;
-const GRIDSIZE: f32 = 60.0;
+pub const START_CHUNKS: u32 = 4; // nav chunks per side of the grid at first, it grows as the colony spreads
+pub const LEVELS: u32 = 4; // the surface and the cave levels under it
+pub const NAV_CHUNK_SIZE: u32 = 12; // cells per side of a pathfinding chunk
 
 const CHUNK_WORLD_SIZE: i32 = CHUNK_SIZE * TILESIZE; // 8 * 12 = 96
 
+// a grid of solid rock reaching the given number of nav chunks out from the origin, not built yet.
+// a diagonal step is only allowed when both straight cells next to it are open, nobody squeezes
+// between two corners of rock
+pub fn new_grid(chunks: u32) -> WorldGrid {
+    let size = chunks * NAV_CHUNK_SIZE;
+    let grid_settings = GridSettingsBuilder::new_3d(size, size, LEVELS)
+        .chunk_size(NAV_CHUNK_SIZE)
+        .add_neighbor_filter(NoCornerCutting)
+        .build();
+
+    let mut grid = WorldGrid::new(&grid_settings);
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..LEVELS { grid.set_nav(UVec3::new(x, y, z), Nav::Impassable); }
+        }
+    }
+    grid
+}
+
+// starts small, the surface opens up as its chunks stream in
 fn generate_path_grid(mut commands: Commands,) {
-    let grid_settings = GridSettingsBuilder::new_2d(GRIDSIZE as u32, GRIDSIZE as u32).chunk_size(TILESIZE as u32).build();
-    commands.spawn(CardinalGrid::new(&grid_settings)).with_child((
+    let mut grid = new_grid(START_CHUNKS);
+    grid.build();
+    commands.spawn(grid).with_child((
         DebugGridBuilder::new(TILESIZE as u32, TILESIZE as u32).enable_cells().build(),
     ));
 }
 
-fn setup_grid_data(
-    perlin: Res<PerlinInstance>,
-    grid: Single<&mut CardinalGrid>,
+fn generate_new_chunk_data(
+    mut desired_chunks:ResMut<DesiredChunks>,
+    camera:Single<(&Transform, &Projection), With<MainCamera>>,
 ) {
-    let mut grid = grid.into_inner();
-    for x in 0..GRIDSIZE as i32 {
-        for y in 0..GRIDSIZE as i32 {
-
-            // let x_noise = ((x * TILESIZE) as f64) / 300.0;
-            // let y_noise = ((y * TILESIZE) as f64) / 300.0;
-            
-            // let noise_value: f64 = perlin.value.get([x_noise, y_noise]);
-
-            // match noise_value {
-            //     (-1.2..=-0.9) => {grid.set_nav(UVec3::new(x as u32, y as u32, 0), Nav::Impassable);},
-            //     (-0.9..=-0.6) => {grid.set_nav(UVec3::new(x as u32, y as u32, 0), Nav::Impassable);},
-            //     (-0.6..=-0.4) => { grid.set_nav(UVec3::new(x as u32, y as u32, 0), Nav::Impassable);},
-            //     _ => {  },
-            // };
+    let (transform, projection) = camera.into_inner();
+    let Projection::Orthographic(orthographic) = projection else { return; };
+
+    // the area is relative to the camera and already scaled by the zoom
+    let center = transform.translation.truncate();
+    let to_chunk = |point: Vec2| (point / CHUNK_WORLD_SIZE as f32).floor().as_ivec2();
+    let min = to_chunk(center + orthographic.area.min);
+    let max = to_chunk(center + orthographic.area.max);
+
+    let camera_chunk = to_chunk(center);
+    desired_chunks.center = (camera_chunk.x, camera_chunk.y);
+    desired_chunks.view = IRect::from_corners(min, max);
+    desired_chunks.keep = IRect::from_corners(min - IVec2::splat(KEEP_MARGIN), max + IVec2::splat(KEEP_MARGIN));
+
+    let switch_at = if desired_chunks.detailed { LOD_SCALE_OUT } else { LOD_SCALE_IN };
+    desired_chunks.detailed = orthographic.scale < switch_at;
+
+    // the world ends at the origin, it only goes on the other way
+    desired_chunks.chunks = HashSet::new();
+    if !desired_chunks.detailed { return; }
+    for chunk_xx in (min.x - RENDER_MARGIN).max(0)..=(max.x + RENDER_MARGIN) {
+        for chunk_yy in (min.y - RENDER_MARGIN).max(0)..=(max.y + RENDER_MARGIN) {
+            desired_chunks.chunks.insert((chunk_xx, chunk_yy));
         }
     }
-    grid.build();
 }
 
-fn generate_new_chunk_data(mut desired_chunks:ResMut<DesiredChunks>,cam_main:Res<MainCameraActualPosition>) {
-    let camera_chunk_x = (cam_main.0.x / CHUNK_WORLD_SIZE as f32).floor() as i32;
-    let camera_chunk_y = (cam_main.0.y / CHUNK_WORLD_SIZE as f32).floor() as i32;
-    
-    desired_chunks.chunks = HashSet::new();
-    for chunk_xx in (camera_chunk_x - RENDER_DISTANCE)..=(camera_chunk_x + RENDER_DISTANCE) {
-        for chunk_yy in (camera_chunk_y - RENDER_DISTANCE)..=(camera_chunk_y + RENDER_DISTANCE) {
-            desired_chunks.chunks.insert((chunk_xx, chunk_yy));
+fn setup_chunk_material(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    // white so the vertex colors come through as they are
+    commands.insert_resource(ChunkMaterial(materials.add(ColorMaterial::from_color(Color::WHITE))));
+}
+
+fn vertex_colors(tints: &[Color]) -> Vec<[f32; 4]> {
+    tints.iter().flat_map(|tint| [tint.to_linear().to_f32_array(); 4]).collect()
+}
+
+// a quad per tile around its cell center, the same order the tiles are stored in
+fn chunk_mesh(tints: &[Color]) -> Mesh {
+    let half = TILESIZE as f32 / 2.0;
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for x in 0..CHUNK_SIZE {
+        for y in 0..CHUNK_SIZE {
+            let center = Vec2::new((x * TILESIZE) as f32, (y * TILESIZE) as f32);
+            let first = positions.len() as u32;
+            positions.extend([
+                [center.x - half, center.y - half, 0.0],
+                [center.x + half, center.y - half, 0.0],
+                [center.x + half, center.y + half, 0.0],
+                [center.x - half, center.y + half, 0.0],
+            ]);
+            indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
         }
     }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors(tints))
+        .with_inserted_indices(Indices::U32(indices))
 }
 
-fn spawn_new_chunks(
-    mut commands:Commands,
-    perlin:Res<PerlinInstance>,
+// missing chunks are sampled on the compute pool, closest to the camera first. chunks that are no
+// longer wanted, or are for another level, are dropped before they finish
+fn queue_chunk_generation(
+    world_gen:Res<WorldGen>,
+    view:Res<ViewLevel>,
     desired_chunks:Res<DesiredChunks>,
-    mut loaded_chunks:ResMut<LoadedChunks>,
+    mut chunk_index:ResMut<ChunkIndex>,
 ) {
-    for &chunk_coords in desired_chunks.chunks.iter() {
-        if !loaded_chunks.chunks.contains(&chunk_coords) {
-            let (chunk_x, chunk_y) = chunk_coords;
-
-            for x in 0..CHUNK_SIZE {
-                for y in 0..CHUNK_SIZE {
-                    let world_x = (chunk_x * CHUNK_SIZE + x) * TILESIZE;
-                    let world_y = (chunk_y * CHUNK_SIZE + y) * TILESIZE;
-
-                    let x_noise = (world_x as f64) / 300.0;
-                    let y_noise = (world_y as f64) / 300.0;
-                    
-                    let noise_value: f64 = perlin.value.get([x_noise, y_noise]);
-
-                    let color = match noise_value {
-                        (-1.2..=-0.9) => { Color::srgb(0.0, 0.0, 0.5)  },
-                        (-0.9..=-0.6) => { Color::srgb(0.0, 0.2, 0.8) },
-                        (-0.6..=-0.4) => { Color::srgb(0.3, 0.5, 1.0) },
-                        (-0.4..=-0.1) => { Color::srgb(1.0, 0.9, 0.6) },
-                        (-0.1..=-0.0) => { Color::srgb(0.56, 0.83, 0.43) },
-                        (-0.0..=0.2) => { Color::srgb(0.4, 0.65, 0.28) },
-                        (0.2..=0.4) => { Color::srgb(0.8, 0.8, 0.8) },
-                        (0.4..=0.6) => { Color::srgb(0.8, 0.8, 0.8)},
-                        (0.6..=0.8) => { Color::srgb(0.8, 0.8, 0.8)},
-                        _ => { Color::srgb(0.3, 0.3, 0.3) },
-                    };
-
-                    commands.spawn((           
-                        Transform::from_xyz(world_x as f32, world_y as f32, 1.0),
-                        Sprite {
-                            color: color,
-                            custom_size: Some(Vec2{x:1. * TILESIZE as f32,y:1. * TILESIZE as f32}),
-                            ..Default::default()
-                        }, GridSquare
-                    ));
-                }
-            }
-            loaded_chunks.chunks.insert(chunk_coords);
-        }
+    if view.is_changed() { chunk_index.pending.clear(); }
+    chunk_index.pending.retain(|(x, y), _| desired_chunks.detailed && desired_chunks.keep.contains(IVec2::new(*x, *y)));
+
+    let (center_x, center_y) = desired_chunks.center;
+    let mut missing: Vec<(i32, i32)> = desired_chunks.chunks.iter()
+        .filter(|coords| !chunk_index.chunks.contains_key(*coords) && !chunk_index.pending.contains_key(*coords))
+        .copied()
+        .collect();
+    missing.sort_by_key(|(x, y)| (x - center_x).pow(2) + (y - center_y).pow(2));
+
+    let pool = AsyncComputeTaskPool::get();
+    for chunk_coords in missing {
+        if chunk_index.pending.len() >= MAX_PENDING_CHUNKS { break; }
+        let (generator, level) = (world_gen.value.clone(), view.0);
+        chunk_index.pending.insert(chunk_coords, pool.spawn(async move { generator.generate_chunk(chunk_coords, level) }));
+    }
+}
+
+// a chunk is one mesh placed at its first cell, spawned once its tiles are ready
+fn spawn_generated_chunks(
+    mut commands:Commands,
+    material:Res<ChunkMaterial>,
+    mut chunk_index:ResMut<ChunkIndex>,
+    mut meshes:ResMut<Assets<Mesh>>,
+) {
+    let mut finished = Vec::new();
+    for (chunk_coords, task) in chunk_index.pending.iter_mut() {
+        if let Some(tiles) = block_on(future::poll_once(task)) { finished.push((*chunk_coords, tiles)); }
+    }
+
+    for (chunk_coords, tiles) in finished {
+        chunk_index.pending.remove(&chunk_coords);
+        let (chunk_x, chunk_y) = chunk_coords;
+        let tints: Vec<Color> = tiles.iter().map(|t| t.color).collect();
+
+        let chunk = commands.spawn((
+            Name::new(format!("Chunk {} {}", chunk_x, chunk_y)),
+            Mesh2d(meshes.add(chunk_mesh(&tints))),
+            MeshMaterial2d(material.0.clone()),
+            Transform::from_xyz((chunk_x * CHUNK_WORLD_SIZE) as f32, (chunk_y * CHUNK_WORLD_SIZE) as f32, 1.0),
+            Chunk { coords: chunk_coords, tiles, tints },
+        )).id();
+        chunk_index.chunks.insert(chunk_coords, chunk);
+    }
+}
+
+// only the colors change after a chunk is spawned
+fn update_chunk_meshes(
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunks: Query<(&Chunk, &Mesh2d), Changed<Chunk>>,
+) {
+    for (chunk, mesh) in &chunks {
+        let Some(mesh) = meshes.get_mut(&mesh.0) else { continue; };
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors(&chunk.tints));
     }
 }
 
 fn cleanup_distant_chunks(
     mut commands: Commands,
     desired_chunks: Res<DesiredChunks>,
-    mut loaded_chunks: ResMut<LoadedChunks>,
-    sprites_query: Query<(Entity, &Transform), With<GridSquare>>,
+    mut chunk_index: ResMut<ChunkIndex>,
 ) {
-    let chunks_to_remove: Vec<(i32, i32)> = loaded_chunks.chunks
-        .difference(&desired_chunks.chunks)
+    let chunks_to_remove: Vec<(i32, i32)> = chunk_index.chunks.keys()
+        .filter(|(x, y)| !desired_chunks.detailed || !desired_chunks.keep.contains(IVec2::new(*x, *y)))
         .cloned()
         .collect();
-        
+
     for chunk_coord in chunks_to_remove {
-        for (entity, transform) in sprites_query.iter() {
-            let chunk_x = (transform.translation.x / CHUNK_WORLD_SIZE as f32).floor() as i32;
-            let chunk_y = (transform.translation.y / CHUNK_WORLD_SIZE as f32).floor() as i32;
-            
-            if (chunk_x, chunk_y) == chunk_coord {
-                commands.entity(entity).despawn();
-            }
+        let Some(chunk) = chunk_index.chunks.remove(&chunk_coord) else { continue; };
+        commands.entity(chunk).despawn();
+    }
+}
+
+// a different level was picked, the chunks stay and their tiles are redrawn for it
+fn redraw_view_level(
+    world_gen: Res<WorldGen>,
+    view: Res<ViewLevel>,
+    mut chunks: Query<&mut Chunk>,
+) {
+    if !view.is_changed() { return; }
+
+    for mut chunk in &mut chunks {
+        if chunk.tiles.first().is_some_and(|t| t.cell.z == view.0 as i32) { continue; }
+        let chunk = chunk.as_mut();
+        for (tile, tint) in chunk.tiles.iter_mut().zip(chunk.tints.iter_mut()) {
+            tile.cell.z = view.0 as i32;
+            tile.color = world_gen.value.tile_color(tile.cell);
+            *tint = tile.color;
         }
-        
-        loaded_chunks.chunks.remove(&chunk_coord);
     }
-}
\ No newline at end of file
+}
+
+fn show_view_level(
+    view: Res<ViewLevel>,
+    mut objects: Query<(Ref<Level>, &mut Visibility)>,
+) {
+    for (level, mut visibility) in &mut objects {
+        if !view.is_changed() && !level.is_changed() { continue; }
+        *visibility = if level.0 == view.0 { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(args: &[&str]) -> MyWorldPlugin {
+        MyWorldPlugin::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn world_and_seed_from_args() {
+        let world = plugin(&["crate", "--world", "Caves", "--seed", "42"]);
+        assert_eq!((world.kind, world.seed), (WorldKind::Caves, 42));
+    }
+
+    #[test]
+    fn defaults_for_missing_or_invalid_args() {
+        let world = plugin(&["crate"]);
+        assert_eq!((world.kind, world.seed), (WorldKind::Islands, 9));
+        let world = plugin(&["crate", "--world", "moon", "--seed", "x", "--world"]);
+        assert_eq!((world.kind, world.seed), (WorldKind::Islands, 9));
+    }
+}
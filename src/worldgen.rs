@@ -0,0 +1,279 @@
+use std::sync::Arc;
+use bevy::prelude::*;
+use noise::{NoiseFn, Perlin};
+
+use crate::world::{GridSquare, CHUNK_SIZE, TILESIZE};
+
+// makes up the world from a seed. the same cell always comes out the same, chunks can be
+// generated in any order and on any thread
+pub trait WorldGenerator: Send + Sync {
+    // surface height at a cell, -1..1. the lowest bands are water, mud sits just above them
+    fn height(&self, cell: IVec2) -> f64;
+
+    fn surface_color(&self, cell: IVec2) -> Color;
+
+    // how wet the ground is, -1..1. the second axis biomes are picked by next to depth
+    fn moisture(&self, cell: IVec2) -> f64;
+
+    // damp rock near the surface grows moss, deep wet pockets grow crystals, the rest is granite
+    fn biome(&self, cell: IVec3) -> Biome {
+        let moisture = self.moisture(cell.truncate());
+        if cell.z <= 1 {
+            if moisture > -0.1 { Biome::MossyCaverns } else { Biome::GraniteDepths }
+        } else if moisture > 0.3 { Biome::CrystalCaves } else { Biome::GraniteDepths }
+    }
+
+    // what the rock of a cell is made of. the height noise doubles as the grain, so the same kind
+    // of rock comes in patches instead of single cells
+    fn rock(&self, cell: IVec3) -> RockKind {
+        let grain = self.height(cell.truncate());
+        match self.biome(cell) {
+            Biome::MossyCaverns => if grain > 0.0 { RockKind::Sandstone } else { RockKind::Soil },
+            Biome::GraniteDepths => if cell.z >= 3 && grain < 0.2 { RockKind::Basalt } else { RockKind::Granite },
+            Biome::CrystalCaves => if grain > 0.3 { RockKind::Basalt } else { RockKind::Granite }
+        }
+    }
+
+    fn cave_color(&self, cell: IVec3) -> Color {
+        self.biome(cell).color(self.height(cell.truncate()) as f32, cell.z)
+    }
+
+    // what digging out a cell leaves behind, the same cell always gives the same
+    fn ore(&self, cell: IVec3) -> Option<&'static str> {
+        let mut roll = cell_roll(cell);
+        for (ore, chance) in self.biome(cell).ores() {
+            if roll < *chance { return Some(ore); }
+            roll -= chance;
+        }
+        None
+    }
+
+    fn tile_color(&self, cell: IVec3) -> Color {
+        if cell.z > 0 { self.cave_color(cell) } else { self.surface_color(cell.truncate()) }
+    }
+
+    // every tile of a chunk on one level
+    fn generate_chunk(&self, chunk_coords: (i32, i32), level: u32) -> Vec<GridSquare> {
+        let (chunk_x, chunk_y) = chunk_coords;
+        let mut tiles = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                let cell = IVec3::new(chunk_x * CHUNK_SIZE + x, chunk_y * CHUNK_SIZE + y, level as i32);
+                tiles.push(GridSquare { cell, color: self.tile_color(cell) });
+            }
+        }
+        tiles
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Biome {
+    MossyCaverns,
+    GraniteDepths,
+    CrystalCaves
+}
+
+impl Biome {
+    // floor palette, shade is the generator's noise -1..1. every level down is a bit darker
+    pub fn color(&self, shade: f32, depth: i32) -> Color {
+        let base = match self {
+            Biome::MossyCaverns => Color::srgb(0.3 + 0.05 * shade, 0.4 + 0.06 * shade, 0.26 + 0.04 * shade),
+            Biome::GraniteDepths => Color::srgb(0.4 + 0.06 * shade, 0.38 + 0.06 * shade, 0.37 + 0.06 * shade),
+            Biome::CrystalCaves => Color::srgb(0.32 + 0.05 * shade, 0.27 + 0.05 * shade, 0.45 + 0.08 * shade)
+        }.to_srgba();
+        let darkness = 1.0 - 0.12 * (depth - 1).max(0) as f32;
+        Color::srgb(base.red * darkness, base.green * darkness, base.blue * darkness)
+    }
+
+    // item keys and the chance a dug out cell drops them, the rest of the time it's just rock
+    pub fn ores(&self) -> &'static [(&'static str, f64)] {
+        match self {
+            Biome::MossyCaverns => &[("coal", 0.35), ("copper_ore", 0.45)],
+            Biome::GraniteDepths => &[("copper_ore", 0.4), ("iron_ore", 0.35)],
+            Biome::CrystalCaves => &[("crystal", 0.3), ("iron_ore", 0.25), ("copper_ore", 0.25)]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RockKind {
+    Soil,
+    Sandstone,
+    Granite,
+    Basalt
+}
+
+impl RockKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RockKind::Soil => "soil",
+            RockKind::Sandstone => "sandstone",
+            RockKind::Granite => "granite",
+            RockKind::Basalt => "basalt"
+        }
+    }
+
+    // how many times longer digging takes than through sandstone
+    pub fn hardness(&self) -> f32 {
+        match self {
+            RockKind::Soil => 0.6,
+            RockKind::Sandstone => 1.0,
+            RockKind::Granite => 1.8,
+            RockKind::Basalt => 2.6
+        }
+    }
+
+    // the pick it takes to dig it, 0 is bare hands
+    pub fn tier(&self) -> u8 {
+        match self {
+            RockKind::Soil | RockKind::Sandstone => 0,
+            RockKind::Granite => 1,
+            RockKind::Basalt => 2
+        }
+    }
+}
+
+// a fixed 0..1 value for every cell
+fn cell_roll(cell: IVec3) -> f64 {
+    let mut hash = (cell.x as u32).wrapping_mul(0x9e37_79b1) ^ (cell.y as u32).wrapping_mul(0x85eb_ca77) ^ (cell.z as u32).wrapping_mul(0xc2b2_ae3d);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    hash as f64 / u32::MAX as f64
+}
+
+// the generator the world was created with
+#[derive(Resource)]
+pub struct WorldGen { pub value: Arc<dyn WorldGenerator> }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldKind {
+    Islands,
+    Lowlands,
+    Caves,
+    Flat // for trying things out, no water anywhere
+}
+
+impl WorldKind {
+    pub fn from_name(name: &str) -> Option<WorldKind> {
+        match name.to_lowercase().as_str() {
+            "islands" => Some(WorldKind::Islands),
+            "lowlands" => Some(WorldKind::Lowlands),
+            "caves" => Some(WorldKind::Caves),
+            "flat" => Some(WorldKind::Flat),
+            _ => None
+        }
+    }
+
+    pub fn generator(&self, seed: u32) -> Arc<dyn WorldGenerator> {
+        match self {
+            WorldKind::Islands => Arc::new(Islands { perlin: Perlin::new(seed) }),
+            WorldKind::Lowlands => Arc::new(Lowlands { perlin: Perlin::new(seed) }),
+            WorldKind::Caves => Arc::new(Caves { perlin: Perlin::new(seed) }),
+            WorldKind::Flat => Arc::new(Flat)
+        }
+    }
+}
+
+// land and lakes from one layer of perlin noise
+pub struct Islands { perlin: Perlin }
+
+impl WorldGenerator for Islands {
+    fn height(&self, cell: IVec2) -> f64 {
+        let x_noise = ((cell.x * TILESIZE) as f64) / 300.0;
+        let y_noise = ((cell.y * TILESIZE) as f64) / 300.0;
+        self.perlin.get([x_noise, y_noise])
+    }
+
+    fn moisture(&self, cell: IVec2) -> f64 {
+        // a slice of the noise far from the one the height comes from
+        self.perlin.get([cell.x as f64 / 40.0, cell.y as f64 / 40.0, 7.5])
+    }
+
+    fn surface_color(&self, cell: IVec2) -> Color {
+        match self.height(cell) {
+            (-1.2..=-0.9) => { Color::srgb(0.0, 0.0, 0.5)  },
+            (-0.9..=-0.6) => { Color::srgb(0.0, 0.2, 0.8) },
+            (-0.6..=-0.4) => { Color::srgb(0.3, 0.5, 1.0) },
+            (-0.4..=-0.1) => { Color::srgb(1.0, 0.9, 0.6) },
+            (-0.1..=-0.0) => { Color::srgb(0.56, 0.83, 0.43) },
+            (-0.0..=0.2) => { Color::srgb(0.4, 0.65, 0.28) },
+            (0.2..=0.4) => { Color::srgb(0.8, 0.8, 0.8) },
+            (0.4..=0.6) => { Color::srgb(0.8, 0.8, 0.8)},
+            (0.6..=0.8) => { Color::srgb(0.8, 0.8, 0.8)},
+            _ => { Color::srgb(0.3, 0.3, 0.3) },
+        }
+    }
+}
+
+// wide, gentle noise with lots of shallow water, the old grid plugin's world
+pub struct Lowlands { perlin: Perlin }
+
+impl WorldGenerator for Lowlands {
+    fn height(&self, cell: IVec2) -> f64 {
+        self.perlin.get([cell.x as f64 / 200.0, cell.y as f64 / 200.0])
+    }
+
+    fn moisture(&self, cell: IVec2) -> f64 {
+        // wetter than anywhere else, half of it is under water anyway
+        (self.perlin.get([cell.x as f64 / 60.0, cell.y as f64 / 60.0, 3.5]) + 0.3).min(1.0)
+    }
+
+    fn surface_color(&self, cell: IVec2) -> Color {
+        match self.height(cell) {
+            v if v < -0.8 => Color::srgb(0.0, 0.0, 0.5),
+            v if v < -0.5 => Color::srgb(0.0, 0.2, 0.8),
+            v if v < -0.1 => Color::srgb(0.3, 0.5, 1.0),
+            v if v < 0.0 => Color::srgb(1.0, 0.9, 0.6),
+            v if v < 0.4 => Color::srgb(0.56, 0.83, 0.43),
+            v if v < 0.8 => Color::srgb(0.4, 0.65, 0.28),
+            _ => Color::srgb(0.8, 0.8, 0.8)
+        }
+    }
+}
+
+// bare rock with a few pools on top, the interesting part is underground where the floors are
+// rougher and darker
+pub struct Caves { perlin: Perlin }
+
+impl WorldGenerator for Caves {
+    fn height(&self, cell: IVec2) -> f64 {
+        let noise = self.perlin.get([cell.x as f64 / 18.0, cell.y as f64 / 18.0]);
+        // mostly high ground, only the deepest dips hold water
+        (noise * 1.4 + 0.5).clamp(-1.0, 1.0)
+    }
+
+    fn moisture(&self, cell: IVec2) -> f64 {
+        self.perlin.get([cell.x as f64 / 24.0, cell.y as f64 / 24.0, 11.5])
+    }
+
+    fn surface_color(&self, cell: IVec2) -> Color {
+        match self.height(cell) {
+            v if v <= -0.6 => Color::srgb(0.1, 0.25, 0.55),
+            v if v <= -0.4 => Color::srgb(0.25, 0.4, 0.6),
+            v => {
+                let shade = 0.45 + 0.15 * v as f32;
+                Color::srgb(shade, shade * 0.97, shade * 0.92)
+            }
+        }
+    }
+
+    fn cave_color(&self, cell: IVec3) -> Color {
+        let roughness = self.perlin.get([cell.x as f64 / 4.0, cell.y as f64 / 4.0, cell.z as f64]) as f32;
+        self.biome(cell).color(roughness * 2.0, cell.z)
+    }
+}
+
+// level grass everywhere, for testing buildings and paths without the terrain in the way
+pub struct Flat;
+
+impl WorldGenerator for Flat {
+    fn height(&self, _cell: IVec2) -> f64 { 0.1 }
+
+    fn moisture(&self, _cell: IVec2) -> f64 { 0.0 }
+
+    fn surface_color(&self, cell: IVec2) -> Color {
+        // a faint checker so the cells can be told apart
+        if (cell.x + cell.y).rem_euclid(2) == 0 { Color::srgb(0.4, 0.65, 0.28) } else { Color::srgb(0.37, 0.61, 0.26) }
+    }
+}